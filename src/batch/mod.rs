@@ -0,0 +1,138 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use crate::{
+    CrawlError, config::ExperimentConfig, stats::Statistics, trajectory::DEFAULT_OUTPUT_ROOT,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress<'a> {
+    pub completed: usize,
+    pub total: usize,
+    pub name: &'a str,
+    pub succeeded: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRun {
+    pub directory: PathBuf,
+    pub statistics: Statistics,
+}
+
+type ProgressCallback = Box<dyn Fn(&Progress) + Send + Sync>;
+
+// runs independent trajectories concurrently, every run is seeded from its own
+// config alone so results do not depend on which worker picks it up or when
+pub struct Batch {
+    // number of worker threads, every available core if 0
+    pub workers: usize,
+    pub output_root: PathBuf,
+    pub progress: Option<ProgressCallback>,
+}
+
+impl Default for Batch {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl std::fmt::Debug for Batch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Batch")
+            .field("workers", &self.workers)
+            .field("output_root", &self.output_root)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+impl Batch {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers,
+            output_root: PathBuf::from(DEFAULT_OUTPUT_ROOT),
+            progress: None,
+        }
+    }
+
+    pub fn with_progress(mut self, progress: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    pub fn worker_count(&self) -> usize {
+        match self.workers {
+            0 => thread::available_parallelism().map_or(1, |count| count.get()),
+            workers => workers,
+        }
+    }
+
+    // builds and runs every config to completion under `output_root`, results
+    // are in input order
+    pub fn run(&self, configs: &[ExperimentConfig]) -> Vec<Result<BatchRun, CrawlError>> {
+        self.map(configs, |_, config| {
+            let mut trajectory = config.build_in(&self.output_root)?;
+            trajectory.run()?;
+
+            Ok(BatchRun {
+                directory: trajectory.directory(),
+                statistics: trajectory.statistics(),
+            })
+        })
+    }
+
+    // applies `job` to every config and its index on the worker pool, results
+    // are in input order
+    pub fn map<T: Send>(
+        &self,
+        configs: &[ExperimentConfig],
+        job: impl Fn(usize, &ExperimentConfig) -> Result<T, CrawlError> + Sync,
+    ) -> Vec<Result<T, CrawlError>> {
+        let total = configs.len();
+        let next = AtomicUsize::new(0);
+        let completed = AtomicUsize::new(0);
+        let results: Vec<Mutex<Option<Result<T, CrawlError>>>> =
+            (0..total).map(|_| Mutex::new(None)).collect();
+
+        thread::scope(|scope| {
+            for _ in 0..self.worker_count().min(total) {
+                scope.spawn(|| {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(config) = configs.get(index) else {
+                            break;
+                        };
+
+                        let result = job(index, config);
+                        let succeeded = result.is_ok();
+                        *results[index].lock().unwrap() = Some(result);
+
+                        let completed = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                        if let Some(progress) = &self.progress {
+                            progress(&Progress {
+                                completed,
+                                total,
+                                name: &config.name,
+                                succeeded,
+                            });
+                        }
+                    }
+                });
+            }
+        });
+
+        results
+            .into_iter()
+            .map(|result| result.into_inner().unwrap().unwrap())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
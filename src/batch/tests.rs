@@ -0,0 +1,150 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    CrawlError,
+    cell::BuiltinRule,
+    config::ExperimentConfig,
+    payoff::PayoffMatrix,
+    trajectory::{Schedule, TrajectoryBuilder, read_history},
+};
+
+use super::Batch;
+
+fn configs(parent: &str, count: u64) -> Vec<ExperimentConfig> {
+    (0..count)
+        .map(|seed| {
+            TrajectoryBuilder::new(format!("{parent}/{seed}"))
+                .max_iterations(6)
+                .dimension((12, 12))
+                .seed(seed)
+                .cooperator_frequency(0.7)
+                .payoff_matrix(PayoffMatrix::new(1.0, 0.0, 0.0, 1.7))
+                .update_rule(BuiltinRule::Fermi { temperature: 0.3 })
+                .schedule(Schedule::RandomSequential)
+                .resolve_config()
+                .unwrap()
+        })
+        .collect()
+}
+
+fn cleanup(parent: &str) {
+    let _ = std::fs::remove_dir_all(std::path::Path::new("trajectories").join(parent));
+}
+
+#[test]
+fn test_results_independent_of_worker_count() -> Result<(), Box<dyn std::error::Error>> {
+    let serial = Batch::new(1).run(&configs("test_batch_serial", 8));
+    let parallel = Batch::new(4).run(&configs("test_batch_parallel", 8));
+
+    assert_eq!(serial.len(), 8);
+    for (serial, parallel) in serial.iter().zip(&parallel) {
+        let (serial, parallel) = (serial.as_ref().unwrap(), parallel.as_ref().unwrap());
+        assert_eq!(serial.statistics, parallel.statistics);
+        assert_eq!(
+            read_history(&serial.directory)?,
+            read_history(&parallel.directory)?
+        );
+    }
+
+    cleanup("test_batch_serial");
+    cleanup("test_batch_parallel");
+    Ok(())
+}
+
+#[test]
+fn test_progress_reports_every_run() -> Result<(), Box<dyn std::error::Error>> {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&reports);
+    let batch = Batch::new(3).with_progress(move |progress| {
+        recorded.lock().unwrap().push((
+            progress.completed,
+            progress.total,
+            progress.name.to_string(),
+        ));
+    });
+
+    let configs = configs("test_batch_progress", 5);
+    let results = batch.run(&configs);
+    assert!(results.iter().all(|result| result.is_ok()));
+
+    let mut reports = reports.lock().unwrap().clone();
+    reports.sort();
+    assert_eq!(
+        reports.iter().map(|report| report.0).collect::<Vec<_>>(),
+        vec![1, 2, 3, 4, 5]
+    );
+    assert!(reports.iter().all(|report| report.1 == 5));
+
+    let mut names: Vec<_> = reports.into_iter().map(|report| report.2).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        configs
+            .iter()
+            .map(|config| config.name.clone())
+            .collect::<Vec<_>>()
+    );
+
+    cleanup("test_batch_progress");
+    Ok(())
+}
+
+#[test]
+fn test_map_keeps_input_order_and_errors() {
+    let configs = configs("test_batch_map", 6);
+    let results = Batch::new(4).map(&configs, |index, config| {
+        if index == 2 {
+            Err(CrawlError::Config(format!("{} failed", config.name)))
+        } else {
+            Ok(index)
+        }
+    });
+
+    assert!(
+        matches!(&results[2], Err(CrawlError::Config(message)) if message == "test_batch_map/2 failed")
+    );
+    for (index, result) in results.iter().enumerate().filter(|(index, _)| *index != 2) {
+        assert_eq!(*result.as_ref().unwrap(), index);
+    }
+}
+
+#[test]
+fn test_duplicate_names_get_distinct_directories() -> Result<(), Box<dyn std::error::Error>> {
+    let mut configs = configs("test_batch_duplicate", 4);
+    for config in &mut configs {
+        config.name = "test_batch_duplicate/shared".to_string();
+    }
+
+    let mut directories: Vec<_> = Batch::new(4)
+        .run(&configs)
+        .into_iter()
+        .map(|result| result.unwrap().directory)
+        .collect();
+    directories.sort();
+    directories.dedup();
+    assert_eq!(directories.len(), 4);
+
+    cleanup("test_batch_duplicate");
+    Ok(())
+}
+
+#[test]
+fn test_custom_output_root() -> Result<(), Box<dyn std::error::Error>> {
+    let output_root = std::env::temp_dir().join("crawl_test_batch_custom_output_root");
+    let _ = std::fs::remove_dir_all(&output_root);
+
+    let mut batch = Batch::new(2);
+    batch.output_root = output_root.clone();
+    for result in batch.run(&configs("test_batch_output_root", 2)) {
+        assert!(result.unwrap().directory.starts_with(&output_root));
+    }
+
+    std::fs::remove_dir_all(&output_root)?;
+    Ok(())
+}
+
+#[test]
+fn test_worker_count_defaults_to_available_cores() {
+    assert!(Batch::default().worker_count() >= 1);
+    assert_eq!(Batch::new(3).worker_count(), 3);
+}
@@ -1,3 +1,7 @@
+mod update_rule;
+
+pub use update_rule::{BuiltinRule, UpdateRule};
+
 #[derive(Debug, PartialEq)]
 pub enum Cell {
     CC(f32),
@@ -34,10 +38,40 @@ impl Cell {
         } = new_fitness;
     }
 
+    // two-bit encoding of the variant shared by hashing and lattice encoding
+    #[inline]
+    pub fn to_code(&self) -> u8 {
+        match self {
+            Cell::CC(_) => 0b00,
+            Cell::CD(_) => 0b01,
+            Cell::DD(_) => 0b10,
+            Cell::DC(_) => 0b11,
+        }
+    }
+
+    // inverse of `to_code`, only the lowest two bits are read and fitness is
+    // reset
+    #[inline]
+    pub fn from_code(code: u8) -> Self {
+        match code & 0b11 {
+            0b00 => Cell::CC(0.0),
+            0b01 => Cell::CD(0.0),
+            0b10 => Cell::DD(0.0),
+            _ => Cell::DC(0.0),
+        }
+    }
+
     pub fn update_strategy(&mut self, to_cooperator: bool) {
+        let was_cooperator = self.is_cooperator();
+        self.set_transition(was_cooperator, to_cooperator);
+    }
+
+    // sets the variant from the strategy held before and after a generation,
+    // keeping the fitness
+    pub fn set_transition(&mut self, was_cooperator: bool, is_cooperator: bool) {
         let fitness = self.get_fitness();
 
-        *self = match (self.is_cooperator(), to_cooperator) {
+        *self = match (was_cooperator, is_cooperator) {
             (true, true) => Cell::CC(fitness),
             (true, false) => Cell::CD(fitness),
             (false, false) => Cell::DD(fitness),
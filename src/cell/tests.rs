@@ -40,6 +40,26 @@ fn test_cell_set_fitness() {
     assert_eq!(cell_dc.get_fitness(), 40.0);
 }
 
+#[test]
+fn test_cell_to_code() {
+    assert_eq!(Cell::CC(1.0).to_code(), 0b00);
+    assert_eq!(Cell::CD(1.0).to_code(), 0b01);
+    assert_eq!(Cell::DD(1.0).to_code(), 0b10);
+    assert_eq!(Cell::DC(1.0).to_code(), 0b11);
+}
+
+#[test]
+fn test_cell_from_code() {
+    for cell in [Cell::CC(1.0), Cell::CD(1.0), Cell::DD(1.0), Cell::DC(1.0)] {
+        let restored = Cell::from_code(cell.to_code());
+        assert_eq!(restored.to_code(), cell.to_code());
+        assert_eq!(restored.get_fitness(), 0.0);
+    }
+
+    // higher bits are ignored
+    assert_eq!(Cell::from_code(0b1110), Cell::DD(0.0));
+}
+
 #[test]
 fn test_cell_update_strategy() {
     // test transitions to cooperator
@@ -102,3 +122,112 @@ fn test_cell_multiple_transitions() {
     cell.update_strategy(true);
     assert_eq!(cell, Cell::CC(10.0));
 }
+
+mod update_rule {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::super::{BuiltinRule, Cell, UpdateRule};
+
+    fn adoption_rate(rule: BuiltinRule, cell: &Cell, neighbours: &[&Cell]) -> f64 {
+        let mut rng = StdRng::seed_from_u64(0);
+        let trials = 10_000;
+        let adopted = (0..trials)
+            .filter(|_| rule.next_strategy(cell, neighbours, &mut rng) != cell.is_cooperator())
+            .count();
+        adopted as f64 / trials as f64
+    }
+
+    #[test]
+    fn test_no_neighbours_keeps_strategy() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let rules = [
+            BuiltinRule::ImitateBest,
+            BuiltinRule::Fermi { temperature: 0.1 },
+            BuiltinRule::ProportionalImitation,
+            BuiltinRule::DeathBirth,
+        ];
+
+        for rule in rules {
+            assert!(rule.next_strategy(&Cell::CC(1.0), &[], &mut rng));
+            assert!(!rule.next_strategy(&Cell::DD(1.0), &[], &mut rng));
+        }
+    }
+
+    #[test]
+    fn test_imitate_best() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let rule = BuiltinRule::ImitateBest;
+
+        let cell = Cell::CC(3.0);
+        assert!(!rule.next_strategy(&cell, &[&Cell::CC(1.0), &Cell::DD(5.0)], &mut rng));
+        assert!(rule.next_strategy(&cell, &[&Cell::DD(2.0), &Cell::DC(4.0)], &mut rng));
+
+        // ties keep the cell's own strategy
+        assert!(rule.next_strategy(&cell, &[&Cell::DD(3.0)], &mut rng));
+    }
+
+    #[test]
+    fn test_fermi_temperature() {
+        let cell = Cell::CC(1.0);
+        let neighbours = [&Cell::DD(2.0)];
+
+        let cold = adoption_rate(BuiltinRule::Fermi { temperature: 0.01 }, &cell, &neighbours);
+        assert_eq!(cold, 1.0);
+
+        let hot = adoption_rate(
+            BuiltinRule::Fermi {
+                temperature: 1000.0,
+            },
+            &cell,
+            &neighbours,
+        );
+        assert!((hot - 0.5).abs() < 0.03, "hot adoption rate was {hot}");
+
+        let worse = adoption_rate(
+            BuiltinRule::Fermi { temperature: 0.01 },
+            &cell,
+            &[&Cell::DD(0.0)],
+        );
+        assert_eq!(worse, 0.0);
+    }
+
+    #[test]
+    fn test_proportional_imitation() {
+        let rule = BuiltinRule::ProportionalImitation;
+        let cell = Cell::CC(1.0);
+
+        // never imitates a less successful neighbour
+        assert_eq!(adoption_rate(rule, &cell, &[&Cell::DD(0.0)]), 0.0);
+
+        // the best neighbour is always imitated by the worst cell
+        assert_eq!(adoption_rate(rule, &cell, &[&Cell::DD(2.0)]), 1.0);
+
+        // a neighbour halfway up the local spread is imitated half the times it is picked
+        let rate = adoption_rate(rule, &cell, &[&Cell::DD(2.0), &Cell::CC(3.0)]);
+        assert!((rate - 0.25).abs() < 0.03, "adoption rate was {rate}");
+    }
+
+    #[test]
+    fn test_death_birth() {
+        let rule = BuiltinRule::DeathBirth;
+
+        // a zero fitness cell is always replaced by its only fit neighbour
+        assert_eq!(adoption_rate(rule, &Cell::CC(0.0), &[&Cell::DD(1.0)]), 1.0);
+
+        // otherwise selection is proportional to fitness
+        let rate = adoption_rate(rule, &Cell::CC(1.0), &[&Cell::DD(3.0)]);
+        assert!((rate - 0.75).abs() < 0.03, "adoption rate was {rate}");
+
+        // without any fitness the pick is uniform
+        let rate = adoption_rate(rule, &Cell::CC(0.0), &[&Cell::DD(0.0)]);
+        assert!((rate - 0.5).abs() < 0.03, "adoption rate was {rate}");
+    }
+
+    #[test]
+    fn test_death_birth_loads_old_name() -> Result<(), serde_json::Error> {
+        let rule: BuiltinRule = serde_json::from_str(r#""MoranBirthDeath""#)?;
+        assert_eq!(rule, BuiltinRule::DeathBirth);
+        assert_eq!(serde_json::to_string(&rule)?, r#""DeathBirth""#);
+        Ok(())
+    }
+}
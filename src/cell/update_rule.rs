@@ -0,0 +1,155 @@
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use super::Cell;
+use crate::CrawlError;
+
+// rules are shared between the threads of a synchronous generation
+pub trait UpdateRule: Sync {
+    // returns whether `cell` plays cooperate in the next generation, given the
+    // neighbours it is allowed to learn from
+    fn next_strategy(&self, cell: &Cell, neighbours: &[&Cell], rng: &mut dyn RngCore) -> bool;
+
+    // a birth-death rule is run by the trajectory, which picks parents from
+    // the whole lattice rather than asking cells for their next strategy
+    fn is_birth_death(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BuiltinRule {
+    ImitateBest,
+    Fermi {
+        temperature: f32,
+    },
+    ProportionalImitation,
+    // the cell dies and is replaced by the offspring of itself or one of its
+    // neighbours, picked in proportion to fitness. this is a death-birth
+    // moran process only under an asynchronous schedule, where cells die one
+    // at a time. runs recorded under the old name still load
+    #[serde(alias = "MoranBirthDeath")]
+    DeathBirth,
+    // a birth-death moran process: a parent drawn from the whole lattice in
+    // proportion to fitness replaces a random imitation neighbour with its
+    // offspring. a generation is one such event per cell whatever the
+    // schedule
+    BirthDeath,
+}
+
+impl BuiltinRule {
+    // the fermi rule divides by its temperature
+    pub fn check(&self) -> Result<(), CrawlError> {
+        if let BuiltinRule::Fermi { temperature } = *self
+            && (temperature <= 0.0 || temperature.is_nan())
+        {
+            return Err(CrawlError::Config(format!(
+                "fermi temperature {temperature} must be positive"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl UpdateRule for BuiltinRule {
+    fn next_strategy(&self, cell: &Cell, neighbours: &[&Cell], rng: &mut dyn RngCore) -> bool {
+        if neighbours.is_empty() {
+            return cell.is_cooperator();
+        }
+
+        match self {
+            BuiltinRule::ImitateBest => imitate_best(cell, neighbours),
+            BuiltinRule::Fermi { temperature } => fermi(cell, neighbours, *temperature, rng),
+            BuiltinRule::ProportionalImitation => proportional_imitation(cell, neighbours, rng),
+            BuiltinRule::DeathBirth => death_birth(cell, neighbours, rng),
+            // never asked, see `is_birth_death`
+            BuiltinRule::BirthDeath => cell.is_cooperator(),
+        }
+    }
+
+    fn is_birth_death(&self) -> bool {
+        matches!(self, BuiltinRule::BirthDeath)
+    }
+}
+
+fn imitate_best(cell: &Cell, neighbours: &[&Cell]) -> bool {
+    // ties are resolved in favour of the cell's own strategy
+    neighbours
+        .iter()
+        .fold(cell, |best, &neighbour| {
+            if neighbour.get_fitness() > best.get_fitness() {
+                neighbour
+            } else {
+                best
+            }
+        })
+        .is_cooperator()
+}
+
+fn fermi(cell: &Cell, neighbours: &[&Cell], temperature: f32, rng: &mut dyn RngCore) -> bool {
+    let model = neighbours[rng.gen_range(0..neighbours.len())];
+
+    let difference = cell.get_fitness() - model.get_fitness();
+    let probability = 1.0 / (1.0 + (difference / temperature).exp());
+
+    if rng.r#gen::<f32>() < probability {
+        model.is_cooperator()
+    } else {
+        cell.is_cooperator()
+    }
+}
+
+fn proportional_imitation(cell: &Cell, neighbours: &[&Cell], rng: &mut dyn RngCore) -> bool {
+    let model = neighbours[rng.gen_range(0..neighbours.len())];
+
+    // normalise by the local fitness spread so the probability lies in [0, 1]
+    let (min, max) = neighbours.iter().fold(
+        (cell.get_fitness(), cell.get_fitness()),
+        |(min, max), neighbour| {
+            (
+                min.min(neighbour.get_fitness()),
+                max.max(neighbour.get_fitness()),
+            )
+        },
+    );
+    let spread = max - min;
+    if spread <= 0.0 {
+        return cell.is_cooperator();
+    }
+
+    let probability = (model.get_fitness() - cell.get_fitness()).max(0.0) / spread;
+
+    if rng.r#gen::<f32>() < probability {
+        model.is_cooperator()
+    } else {
+        cell.is_cooperator()
+    }
+}
+
+fn death_birth(cell: &Cell, neighbours: &[&Cell], rng: &mut dyn RngCore) -> bool {
+    let candidates = std::iter::once(cell).chain(neighbours.iter().copied());
+
+    // shift negative payoffs so every candidate has a non-negative weight
+    let min = candidates
+        .clone()
+        .map(Cell::get_fitness)
+        .fold(0.0_f32, f32::min);
+    let total: f32 = candidates.clone().map(|c| c.get_fitness() - min).sum();
+
+    if total <= 0.0 {
+        let index = rng.gen_range(0..=neighbours.len());
+        return candidates.clone().nth(index).unwrap().is_cooperator();
+    }
+
+    let mut target = rng.r#gen::<f32>() * total;
+    for candidate in candidates.clone() {
+        target -= candidate.get_fitness() - min;
+        if target < 0.0 {
+            return candidate.is_cooperator();
+        }
+    }
+
+    // only reachable through rounding, fall back to the last candidate
+    candidates.last().unwrap().is_cooperator()
+}
@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CrawlError,
+    cell::BuiltinRule,
+    grid::{self, Geometry, RngSettings},
+    neighbourhood::NeighbourhoodShape,
+    payoff::Payoff,
+    render::GifOptions,
+    trajectory::{self, DEFAULT_OUTPUT_ROOT, Schedule, Trajectory, TrajectoryBuilder},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridConfig {
+    pub dimension: (i32, i32),
+    // stacked lattices of `dimension`, more than one needs a cubic geometry
+    #[serde(default = "default_layers")]
+    pub layers: i32,
+    pub wrapped: bool,
+    #[serde(default)]
+    pub geometry: Geometry,
+    #[serde(default)]
+    pub rng_settings: Option<RngSettings>,
+}
+
+// complete description of a run, this is also the shape of `metadata.json`
+// minus the trajectory id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    pub name: String,
+    pub max_iterations: usize,
+    pub grid: GridConfig,
+    // snapshot of generation zero for a lattice the rng settings do not
+    // generate, relative paths in a config file are taken from its directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_snapshot: Option<PathBuf>,
+    pub neighbourhood: NeighbourhoodShape,
+    // neighbours a cell learns from, the interaction neighbourhood if unset
+    #[serde(default)]
+    pub imitation_neighbourhood: Option<NeighbourhoodShape>,
+    // whether every cell also plays against and imitates itself
+    #[serde(default)]
+    pub include_self: bool,
+    // self-imitation where it differs from `include_self`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imitation_include_self: Option<bool>,
+    pub payoff: Payoff,
+    #[serde(default = "default_update_rule")]
+    pub update_rule: BuiltinRule,
+    #[serde(default)]
+    pub schedule: Schedule,
+    #[serde(default)]
+    pub stop_on_cycle: bool,
+    #[serde(default)]
+    pub detect_cycles: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_interval: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gif: Option<GifOptions>,
+}
+
+fn default_layers() -> i32 {
+    1
+}
+
+fn default_update_rule() -> BuiltinRule {
+    BuiltinRule::ImitateBest
+}
+
+impl ExperimentConfig {
+    pub fn from_toml_str(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    pub fn from_json_str(contents: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(contents)
+    }
+
+    // picks the format from the file extension, so a trajectory's
+    // `metadata.json` can be loaded directly
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CrawlError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(CrawlError::io(path))?;
+
+        let mut config = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml_str(&contents)?,
+            Some("json") => Self::from_json_str(&contents)?,
+            _ => {
+                return Err(CrawlError::Config(format!(
+                    "cannot infer config format of {}, expected .toml or .json",
+                    path.display()
+                )));
+            }
+        };
+
+        if let Some(initial_snapshot) = &mut config.initial_snapshot
+            && initial_snapshot.is_relative()
+            && let Some(directory) = path.parent()
+        {
+            *initial_snapshot = directory.join(&*initial_snapshot);
+        }
+
+        Ok(config)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string_pretty(self)
+    }
+
+    // catches values that deserialise fine but cannot be run
+    pub fn validate(&self) -> Result<(), CrawlError> {
+        trajectory::check_name(&self.name)?;
+
+        grid::layered_cell_count(self.grid.dimension, self.grid.layers)?;
+        self.grid.geometry.check_dimension(
+            self.grid.dimension,
+            self.grid.layers,
+            self.grid.wrapped,
+        )?;
+        if let Some(rng_settings) = &self.grid.rng_settings
+            && !(0.0..=1.0).contains(&rng_settings.cooperator_frequency)
+        {
+            return Err(CrawlError::InvalidFrequency(
+                rng_settings.cooperator_frequency,
+            ));
+        }
+
+        for shape in std::iter::once(&self.neighbourhood).chain(&self.imitation_neighbourhood) {
+            shape.check_geometry(self.grid.geometry)?;
+            shape.check_dimension(self.grid.dimension, self.grid.wrapped)?;
+            if let NeighbourhoodShape::Circular { radius } = *shape
+                && !(radius >= 0.0 && radius.is_finite())
+            {
+                return Err(CrawlError::Config(format!(
+                    "circular neighbourhood radius {radius} must be finite and non-negative"
+                )));
+            }
+        }
+
+        self.payoff.spatial.check_dimension(self.grid.dimension)?;
+
+        self.update_rule.check()?;
+
+        if self.snapshot_interval == Some(0) {
+            return Err(CrawlError::Config(
+                "snapshot_interval must be positive".to_string(),
+            ));
+        }
+        if self.gif.is_some_and(|gif| gif.stride == 0) {
+            return Err(CrawlError::Config(
+                "gif stride must be positive".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn build(&self) -> Result<Trajectory, CrawlError> {
+        self.build_in(DEFAULT_OUTPUT_ROOT)
+    }
+
+    // builds the trajectory under `output_root` instead of `trajectories/`
+    pub fn build_in(&self, output_root: impl AsRef<Path>) -> Result<Trajectory, CrawlError> {
+        TrajectoryBuilder::from_config(self.clone())
+            .output_root(output_root)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,279 @@
+use crate::{
+    CrawlError,
+    cell::BuiltinRule,
+    grid::{Geometry, RngSettings},
+    neighbourhood::{CubicShell, Direction, Neighbourhood, NeighbourhoodShape},
+    payoff::{Payoff, PayoffMatrix, SpatialPayoff},
+    render::GifOptions,
+    trajectory::{Schedule, TrajectoryBuilder},
+};
+
+use super::ExperimentConfig;
+
+const TOML_CONFIG: &str = r#"
+name = "test_config_toml"
+max_iterations = 50
+neighbourhood = ["Up", "Right", "Down", "Left"]
+
+[grid]
+dimension = [30, 40]
+wrapped = true
+rng_settings = { seed = 42, cooperator_frequency = 0.9 }
+
+[payoff]
+matrix = { c_c = 1.0, c_d = 0.0, d_d = 0.0, d_c = 1.85 }
+spatial = "None"
+"#;
+
+fn full_config(name: &str) -> ExperimentConfig {
+    TrajectoryBuilder::new(name)
+        .max_iterations(6)
+        .dimension((12, 12))
+        .seed(9)
+        .cooperator_frequency(0.8)
+        .neighbourhood(Neighbourhood::circular(1.5).with_self(true))
+        .imitation_neighbourhood(Neighbourhood::moore_radius(2))
+        .payoff(Payoff::with_spatial(
+            PayoffMatrix::new(1.0, 0.0, 0.0, 1.6),
+            SpatialPayoff::Constant { value: 0.1 },
+        ))
+        .update_rule(BuiltinRule::Fermi { temperature: 0.2 })
+        .schedule(Schedule::RandomPermutation)
+        .stop_on_cycle(true)
+        .snapshot_interval(3)
+        .gif(GifOptions::default())
+        .resolve_config()
+        .unwrap()
+}
+
+fn cleanup(config: &ExperimentConfig) {
+    let _ = std::fs::remove_dir_all(std::path::Path::new("trajectories").join(&config.name));
+}
+
+#[test]
+fn test_from_toml_str_defaults() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ExperimentConfig::from_toml_str(TOML_CONFIG)?;
+
+    assert_eq!(config.name, "test_config_toml");
+    assert_eq!(config.max_iterations, 50);
+    assert_eq!(config.grid.dimension, (30, 40));
+    assert_eq!(
+        config.grid.rng_settings,
+        Some(RngSettings::new(Some(42), 0.9)?)
+    );
+    assert_eq!(
+        config.neighbourhood,
+        NeighbourhoodShape::Directions(vec![
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ])
+    );
+    assert_eq!(config.payoff.matrix, PayoffMatrix::new(1.0, 0.0, 0.0, 1.85));
+
+    assert_eq!(config.update_rule, BuiltinRule::ImitateBest);
+    assert_eq!(config.schedule, Schedule::Synchronous);
+    assert!(!config.stop_on_cycle);
+    assert_eq!(config.snapshot_interval, None);
+    assert_eq!(config.gif, None);
+    Ok(())
+}
+
+#[test]
+fn test_toml_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let config = full_config("test_toml_roundtrip");
+    let toml = config.to_toml_string()?;
+    assert_eq!(ExperimentConfig::from_toml_str(&toml)?, config);
+    Ok(())
+}
+
+#[test]
+fn test_json_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let config = full_config("test_json_roundtrip");
+    let json = serde_json::to_string(&config)?;
+    assert_eq!(ExperimentConfig::from_json_str(&json)?, config);
+    Ok(())
+}
+
+#[test]
+fn test_from_file_rejects_unknown_extension() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("crawl_test_from_file_rejects_unknown_extension.yaml");
+    std::fs::write(&path, TOML_CONFIG)?;
+
+    let result = ExperimentConfig::from_file(&path);
+    std::fs::remove_file(&path)?;
+
+    assert!(matches!(result, Err(CrawlError::Config(_))));
+    Ok(())
+}
+
+#[test]
+fn test_from_file_resolves_initial_snapshot() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("crawl_test_from_file_resolves_initial_snapshot.toml");
+    std::fs::write(
+        &path,
+        format!("initial_snapshot = \"start.bin\"\n{TOML_CONFIG}"),
+    )?;
+
+    let result = ExperimentConfig::from_file(&path);
+    std::fs::remove_file(&path)?;
+
+    assert_eq!(
+        result?.initial_snapshot,
+        Some(std::env::temp_dir().join("start.bin"))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_metadata_roundtrips_into_config() -> Result<(), Box<dyn std::error::Error>> {
+    let config = full_config("test_metadata_roundtrips_into_config");
+    let trajectory = config.build()?;
+
+    let restored = ExperimentConfig::from_file(trajectory.directory().join("metadata.json"))?;
+    assert_eq!(restored, config);
+
+    cleanup(&config);
+    Ok(())
+}
+
+#[test]
+fn test_metadata_reexecutes_exactly() -> Result<(), Box<dyn std::error::Error>> {
+    let config = full_config("test_metadata_reexecutes_exactly");
+    let mut original = config.build()?;
+    original.run()?;
+
+    let mut restored = ExperimentConfig::from_file(original.directory().join("metadata.json"))?;
+    restored.name = format!("{}_rerun", config.name);
+    let mut rerun = restored.build()?;
+    rerun.run()?;
+
+    assert_eq!(rerun.statistics(), original.statistics());
+    assert_eq!(
+        rerun.history(),
+        original.history(),
+        "re-executed run diverged"
+    );
+
+    cleanup(&config);
+    cleanup(&restored);
+    Ok(())
+}
+
+#[test]
+fn test_validate_rejects_unrunnable_configs() {
+    let valid = full_config("test_validate_rejects_unrunnable_configs");
+    assert!(valid.validate().is_ok());
+
+    let mut config = valid.clone();
+    config.grid.dimension = (0, 12);
+    assert!(matches!(
+        config.build(),
+        Err(CrawlError::InvalidDimension((0, 12)))
+    ));
+
+    // deserialised settings bypass `RngSettings::new`
+    let mut config = valid.clone();
+    config
+        .grid
+        .rng_settings
+        .as_mut()
+        .unwrap()
+        .cooperator_frequency = 2.0;
+    assert!(matches!(
+        config.validate(),
+        Err(CrawlError::InvalidFrequency(_))
+    ));
+
+    let mut config = valid.clone();
+    config.update_rule = BuiltinRule::Fermi { temperature: 0.0 };
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+
+    let mut config = valid.clone();
+    config.neighbourhood = NeighbourhoodShape::Circular { radius: f64::NAN };
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+
+    // the imitation neighbourhood reaches two cells either way
+    let mut config = valid.clone();
+    config.grid.dimension = (4, 12);
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+
+    let mut config = valid.clone();
+    config.grid.geometry = Geometry::Hexagonal;
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+    config.neighbourhood = NeighbourhoodShape::Hexagonal { radius: 1 };
+    config.imitation_neighbourhood = None;
+    assert!(config.validate().is_ok());
+
+    let mut config = valid.clone();
+    config.grid.geometry = Geometry::Triangular;
+    config.grid.dimension = (12, 13);
+    config.neighbourhood = NeighbourhoodShape::Triangular { vertices: false };
+    config.imitation_neighbourhood = None;
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+
+    let mut config = valid.clone();
+    config.grid.layers = 4;
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+    config.grid.geometry = Geometry::Cubic;
+    config.neighbourhood = NeighbourhoodShape::Cubic {
+        shell: CubicShell::Edges,
+    };
+    config.imitation_neighbourhood = None;
+    assert!(config.validate().is_ok());
+    config.grid.layers = 0;
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+
+    // a map that does not cover the lattice would index past its values
+    let mut config = valid.clone();
+    config.payoff.spatial = SpatialPayoff::Map {
+        dimension: (5, 5),
+        values: vec![1.0, 2.0],
+    };
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+    config.payoff.spatial = SpatialPayoff::RadialHotspot {
+        centre: (0.0, 0.0),
+        amplitude: 1.0,
+        radius: 0.0,
+    };
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+
+    let mut config = valid.clone();
+    config.snapshot_interval = Some(0);
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+
+    let mut config = valid.clone();
+    config.name = String::new();
+    assert!(matches!(config.validate(), Err(CrawlError::Config(_))));
+
+    // nothing is written for a rejected config
+    assert!(
+        !std::path::Path::new("trajectories/test_validate_rejects_unrunnable_configs").exists()
+    );
+}
+
+#[test]
+fn test_from_file_missing() {
+    assert!(matches!(
+        ExperimentConfig::from_file("does/not/exist.toml"),
+        Err(CrawlError::Io { .. })
+    ));
+}
+
+#[test]
+fn test_neighbourhood_shapes_parse_from_toml() -> Result<(), Box<dyn std::error::Error>> {
+    let toml = TOML_CONFIG.replace(
+        r#"neighbourhood = ["Up", "Right", "Down", "Left"]"#,
+        "neighbourhood = { Moore = { radius = 2 } }",
+    );
+    let config = ExperimentConfig::from_toml_str(&toml)?;
+    assert_eq!(
+        config.neighbourhood,
+        NeighbourhoodShape::Moore { radius: 2 }
+    );
+
+    let json = serde_json::to_string(&config)?;
+    assert!(json.contains(r#""neighbourhood":{"Moore":{"radius":2}}"#));
+    Ok(())
+}
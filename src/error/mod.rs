@@ -0,0 +1,100 @@
+use std::{fmt, io, path::PathBuf};
+
+#[derive(Debug)]
+pub enum CrawlError {
+    // initial cooperator frequency outside [0, 1]
+    InvalidFrequency(f64),
+    // a lattice side that is zero or negative
+    InvalidDimension((i32, i32)),
+    // a lattice whose cell count, or an output image of it, does not fit
+    DimensionOverflow((i32, i32)),
+    Io { path: PathBuf, source: io::Error },
+    Json(serde_json::Error),
+    TomlDeserialize(toml::de::Error),
+    TomlSerialize(toml::ser::Error),
+    Png(png::EncodingError),
+    Gif(gif::EncodingError),
+    // malformed snapshot, statistics, history or spatial payoff data
+    Parse(String),
+    // an experiment or sweep that is well formed but cannot be run
+    Config(String),
+}
+
+impl CrawlError {
+    // attaches `path` to an i/o error, for use with `map_err`
+    pub fn io(path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+        move |source| Self::Io { path, source }
+    }
+}
+
+impl fmt::Display for CrawlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidFrequency(frequency) => write!(
+                f,
+                "cooperator frequency {frequency} must lie between 0.0 and 1.0"
+            ),
+            Self::InvalidDimension((rows, cols)) => {
+                write!(f, "lattice dimension {rows}x{cols} must be positive")
+            }
+            Self::DimensionOverflow((rows, cols)) => {
+                write!(f, "lattice dimension {rows}x{cols} is too large")
+            }
+            Self::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Json(error) => write!(f, "json: {error}"),
+            Self::TomlDeserialize(error) => write!(f, "toml: {error}"),
+            Self::TomlSerialize(error) => write!(f, "toml: {error}"),
+            Self::Png(error) => write!(f, "png: {error}"),
+            Self::Gif(error) => write!(f, "gif: {error}"),
+            Self::Parse(message) | Self::Config(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for CrawlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Json(error) => Some(error),
+            Self::TomlDeserialize(error) => Some(error),
+            Self::TomlSerialize(error) => Some(error),
+            Self::Png(error) => Some(error),
+            Self::Gif(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for CrawlError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl From<toml::de::Error> for CrawlError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::TomlDeserialize(error)
+    }
+}
+
+impl From<toml::ser::Error> for CrawlError {
+    fn from(error: toml::ser::Error) -> Self {
+        Self::TomlSerialize(error)
+    }
+}
+
+impl From<png::EncodingError> for CrawlError {
+    fn from(error: png::EncodingError) -> Self {
+        Self::Png(error)
+    }
+}
+
+impl From<gif::EncodingError> for CrawlError {
+    fn from(error: gif::EncodingError) -> Self {
+        Self::Gif(error)
+    }
+}
+
+#[cfg(test)]
+mod tests;
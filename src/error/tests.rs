@@ -0,0 +1,46 @@
+use std::error::Error;
+
+use super::CrawlError;
+
+#[test]
+fn test_io_attaches_path() {
+    let error = std::fs::read("does/not/exist.bin")
+        .map_err(CrawlError::io("does/not/exist.bin"))
+        .unwrap_err();
+
+    match &error {
+        CrawlError::Io { path, source } => {
+            assert_eq!(path, std::path::Path::new("does/not/exist.bin"));
+            assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+        }
+        error => panic!("unexpected error {error:?}"),
+    }
+    assert!(error.to_string().starts_with("does/not/exist.bin: "));
+    assert!(error.source().is_some());
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(
+        CrawlError::InvalidFrequency(1.5).to_string(),
+        "cooperator frequency 1.5 must lie between 0.0 and 1.0"
+    );
+    assert_eq!(
+        CrawlError::InvalidDimension((0, 4)).to_string(),
+        "lattice dimension 0x4 must be positive"
+    );
+    assert_eq!(
+        CrawlError::Config("name must not be empty".to_string()).to_string(),
+        "name must not be empty"
+    );
+}
+
+#[test]
+fn test_from_serialisation_errors() {
+    let error: CrawlError = serde_json::from_str::<u32>("nope").unwrap_err().into();
+    assert!(matches!(error, CrawlError::Json(_)));
+    assert!(error.source().is_some());
+
+    let error: CrawlError = toml::from_str::<toml::Table>("= 1").unwrap_err().into();
+    assert!(matches!(error, CrawlError::TomlDeserialize(_)));
+}
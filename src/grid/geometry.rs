@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+use crate::CrawlError;
+
+// shape of the cells tiling the lattice, cells are always stored row-major by
+// `(row, col)` and only adjacency and rendering depend on the geometry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Geometry {
+    #[default]
+    Square,
+    // hexagons in axial coordinates: each row sits half a cell right of the
+    // one above, so the lattice is a rhombus and every cell has the same six
+    // neighbour offsets. wrapping both axes gives a proper hexagonal torus
+    Hexagonal,
+    // triangles pointing up where `row + col` is even and down elsewhere,
+    // neighbour offsets depend on the orientation
+    Triangular,
+    // cubes stacked in layers of square lattices
+    Cubic,
+}
+
+impl Geometry {
+    // only cubic lattices stack layers. wrapping must map up triangles onto
+    // up triangles, which needs an even number of rows and columns, and a
+    // wrapped cubic lattice needs three cells along every axis or the cubes
+    // either side of a cell are one and the same
+    pub fn check_dimension(
+        self,
+        dimension: (i32, i32),
+        layers: i32,
+        wrapped: bool,
+    ) -> Result<(), CrawlError> {
+        if layers > 1 && self != Geometry::Cubic {
+            return Err(CrawlError::Config(format!(
+                "a {self:?} lattice cannot have {layers} layers"
+            )));
+        }
+        if self == Geometry::Triangular && wrapped && (dimension.0 % 2 != 0 || dimension.1 % 2 != 0)
+        {
+            return Err(CrawlError::Config(format!(
+                "a wrapped triangular lattice needs even sides, got {}x{}",
+                dimension.0, dimension.1
+            )));
+        }
+        if self == Geometry::Cubic && wrapped && dimension.0.min(dimension.1).min(layers) < 3 {
+            return Err(CrawlError::Config(format!(
+                "a wrapped cubic lattice needs at least 3 cells along every axis, got {layers}x{}x{}",
+                dimension.0, dimension.1
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn points_up(row: i32, col: i32) -> bool {
+        (row + col).rem_euclid(2) == 0
+    }
+}
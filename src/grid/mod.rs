@@ -1,65 +1,230 @@
+mod geometry;
 mod rng;
 
-use crate::cell::Cell;
-use rand::SeedableRng;
-pub use rng::RngSettings;
+use crate::{CrawlError, cell::Cell};
+pub use geometry::Geometry;
+pub use rng::{Initialisation, RngSettings};
 
-use ahash::AHasher;
-use std::hash::Hasher;
+// fnv-1a parameters for the lattice hash
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
 
+// longest lattice side, leaving room to add neighbour offsets to any
+// coordinate without overflowing i32
+pub const MAX_SIDE: i32 = 1 << 30;
+
+// number of cells in a lattice of `dimension`, rejecting empty lattices and
+// cell counts that overflow
+pub fn cell_count(dimension: (i32, i32)) -> Result<usize, CrawlError> {
+    layered_cell_count(dimension, 1)
+}
+
+// number of cells in `layers` stacked lattices of `dimension`
+pub fn layered_cell_count(dimension: (i32, i32), layers: i32) -> Result<usize, CrawlError> {
+    if dimension.0 <= 0 || dimension.1 <= 0 {
+        return Err(CrawlError::InvalidDimension(dimension));
+    }
+    if layers <= 0 {
+        return Err(CrawlError::Config(format!(
+            "layer count {layers} must be positive"
+        )));
+    }
+    if dimension.0 > MAX_SIDE || dimension.1 > MAX_SIDE || layers > MAX_SIDE {
+        return Err(CrawlError::DimensionOverflow(dimension));
+    }
+
+    (dimension.0 as usize)
+        .checked_mul(dimension.1 as usize)
+        .and_then(|cells| cells.checked_mul(layers as usize))
+        .ok_or(CrawlError::DimensionOverflow(dimension))
+}
+
+// the random lattice of `rng_settings`, or a lone defector in the centre
+fn generate_lattice(total_cells: usize, rng_settings: Option<&RngSettings>) -> Vec<Cell> {
+    match rng_settings {
+        Some(rng_settings) => rng_settings
+            .initial_strategies(total_cells)
+            .into_iter()
+            .map(Cell::new)
+            .collect(),
+        None => {
+            let center_index = total_cells / 2;
+            (0..total_cells)
+                .map(|i| Cell::new(i != center_index))
+                .collect()
+        }
+    }
+}
+
+// `layers` lattices of `dimension` are stacked into a cubic lattice, the
+// lattice holds layer after layer and positions are `(layer, row, col)`.
+// the two-dimensional methods address the first layer
 #[derive(Debug)]
 pub struct Grid {
     pub dimension: (i32, i32),
+    pub layers: i32,
     pub wrapped: bool,
+    pub geometry: Geometry,
     pub rng_settings: Option<RngSettings>,
     pub lattice: Vec<Cell>,
 }
 
 impl Grid {
-    pub fn new(dimension: (i32, i32), wrapped: bool, rng_settings: Option<RngSettings>) -> Self {
-        let total_cells = dimension.0 * dimension.1;
-        let lattice: Vec<Cell> = match &rng_settings {
-            Some(rng_settings) => {
-                let rng = rand::rngs::StdRng::seed_from_u64(rng_settings.seed);
-                let threshold = rng_settings.cooperator_frequency;
-                (0..total_cells)
-                    .map(|_| {
-                        let random_value = rand::Rng::r#gen::<f64>(&mut rng.clone());
-                        Cell::new(random_value < threshold)
-                    })
-                    .collect()
-            }
-            None => {
-                println!("no rng settings detected, defaulting to lone defector");
-                let center_index = total_cells / 2;
-                (0..total_cells)
-                    .map(|i| Cell::new(i != center_index))
-                    .collect()
-            }
-        };
+    pub fn new(
+        dimension: (i32, i32),
+        wrapped: bool,
+        rng_settings: Option<RngSettings>,
+    ) -> Result<Self, CrawlError> {
+        Self::new_layered(dimension, 1, wrapped, rng_settings)
+    }
+
+    // a cubic lattice of `layers` layers, each of `dimension`
+    pub fn cubic(
+        dimension: (i32, i32),
+        layers: i32,
+        wrapped: bool,
+        rng_settings: Option<RngSettings>,
+    ) -> Result<Self, CrawlError> {
+        Self::new_layered(dimension, layers, wrapped, rng_settings)?.with_geometry(Geometry::Cubic)
+    }
 
-        Grid {
+    fn new_layered(
+        dimension: (i32, i32),
+        layers: i32,
+        wrapped: bool,
+        rng_settings: Option<RngSettings>,
+    ) -> Result<Self, CrawlError> {
+        let total_cells = layered_cell_count(dimension, layers)?;
+        if rng_settings.is_none() {
+            println!("no rng settings detected, defaulting to lone defector");
+        }
+        let lattice = generate_lattice(total_cells, rng_settings.as_ref());
+
+        Ok(Grid {
             dimension,
+            layers,
             wrapped,
+            geometry: Geometry::default(),
             rng_settings,
             lattice,
+        })
+    }
+
+    // rebuilds a grid from the output of `encode_lattice`
+    pub fn from_encoded(
+        dimension: (i32, i32),
+        wrapped: bool,
+        rng_settings: Option<RngSettings>,
+        encoded: &[u8],
+    ) -> Result<Self, CrawlError> {
+        Self::from_encoded_layers(dimension, 1, wrapped, rng_settings, encoded)
+    }
+
+    pub fn from_encoded_layers(
+        dimension: (i32, i32),
+        layers: i32,
+        wrapped: bool,
+        rng_settings: Option<RngSettings>,
+        encoded: &[u8],
+    ) -> Result<Self, CrawlError> {
+        let total_cells = layered_cell_count(dimension, layers)?;
+        if encoded.len() != total_cells.div_ceil(4) {
+            return Err(CrawlError::Parse(format!(
+                "encoded lattice holds {} bytes, expected {} for a {}x{}x{} grid",
+                encoded.len(),
+                total_cells.div_ceil(4),
+                layers,
+                dimension.0,
+                dimension.1
+            )));
         }
+
+        let lattice = (0..total_cells)
+            .map(|i| Cell::from_code(encoded[i / 4] >> (2 * (i % 4))))
+            .collect();
+
+        Ok(Grid {
+            dimension,
+            layers,
+            wrapped,
+            geometry: Geometry::default(),
+            rng_settings,
+            lattice,
+        })
+    }
+
+    // whether the lattice is still the one its rng settings generate, or the
+    // lone defector without them
+    pub fn is_generated(&self) -> bool {
+        let generated = generate_lattice(self.lattice.len(), self.rng_settings.as_ref());
+        self.lattice
+            .iter()
+            .map(Cell::to_code)
+            .eq(generated.iter().map(Cell::to_code))
     }
 
+    // the same lattice tiled by `geometry` instead of squares
+    pub fn with_geometry(mut self, geometry: Geometry) -> Result<Self, CrawlError> {
+        geometry.check_dimension(self.dimension, self.layers, self.wrapped)?;
+        self.geometry = geometry;
+        Ok(self)
+    }
+
+    #[inline]
     fn get_index(&self, row: i32, col: i32) -> Option<usize> {
-        let num_rows = self.dimension.0;
-        let num_cols = self.dimension.1;
+        self.get_index_3d(0, row, col)
+    }
 
-        if self.wrapped {
-            let wrapped_row = ((row % num_rows) + num_rows) % num_rows;
-            let wrapped_col = ((col % num_cols) + num_cols) % num_cols;
+    // wrapping is a plain modulo in every geometry: hexagonal rows are already
+    // sheared into axial coordinates and triangular sides are even
+    pub fn get_index_3d(&self, layer: i32, row: i32, col: i32) -> Option<usize> {
+        let (num_rows, num_cols) = self.dimension;
+        let num_layers = self.layers;
 
-            Some((wrapped_row * num_cols + wrapped_col) as usize)
-        } else if row >= 0 && row < num_rows && col >= 0 && col < num_cols {
-            Some((row * num_cols + col) as usize)
+        let (layer, row, col) = if self.wrapped {
+            (
+                layer.rem_euclid(num_layers),
+                row.rem_euclid(num_rows),
+                col.rem_euclid(num_cols),
+            )
+        } else if (0..num_layers).contains(&layer)
+            && (0..num_rows).contains(&row)
+            && (0..num_cols).contains(&col)
+        {
+            (layer, row, col)
         } else {
-            None
-        }
+            return None;
+        };
+
+        // the flat index can exceed i32 on large lattices
+        let plane = num_rows as usize * num_cols as usize;
+        Some(layer as usize * plane + row as usize * num_cols as usize + col as usize)
+    }
+
+    // row and column of `index` within its layer
+    #[inline]
+    pub fn get_coordinates(&self, index: usize) -> (i32, i32) {
+        let (_, row, col) = self.get_position(index);
+        (row, col)
+    }
+
+    #[inline]
+    pub fn get_position(&self, index: usize) -> (i32, i32, i32) {
+        let num_cols = self.dimension.1 as usize;
+        let plane = self.dimension.0 as usize * num_cols;
+        (
+            (index / plane) as i32,
+            (index % plane / num_cols) as i32,
+            (index % num_cols) as i32,
+        )
+    }
+
+    // maps possibly out-of-range coordinates onto the lattice, wrapping if
+    // enabled
+    #[inline]
+    pub fn resolve(&self, row: i32, col: i32) -> Option<(i32, i32)> {
+        self.get_index(row, col)
+            .map(|index| self.get_coordinates(index))
     }
 
     #[inline]
@@ -68,26 +233,44 @@ impl Grid {
             .and_then(|index| self.lattice.get(index))
     }
 
+    #[inline]
+    pub fn get_cell_3d(&self, layer: i32, row: i32, col: i32) -> Option<&Cell> {
+        self.get_index_3d(layer, row, col)
+            .and_then(|index| self.lattice.get(index))
+    }
+
     #[inline]
     pub fn get_cell_mut(&mut self, row: i32, col: i32) -> Option<&mut Cell> {
         self.get_index(row, col)
             .and_then(|index| self.lattice.get_mut(index))
     }
 
+    // coordinates of the first layer
+    pub fn coordinates(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (num_rows, num_cols) = self.dimension;
+        (0..num_rows).flat_map(move |row| (0..num_cols).map(move |col| (row, col)))
+    }
+
+    // unlike a randomly keyed hasher this is identical across processes and
+    // machines, so recorded histories can be verified by replaying a run.
+    // every layer is hashed in order, a single layer hashes as it always has
     pub fn get_lattice_hash(&self) -> u64 {
-        let mut hasher = AHasher::default();
-
-        for cell in &self.lattice {
-            let value = match cell {
-                Cell::CC(_) => 0b00,
-                Cell::CD(_) => 0b01,
-                Cell::DD(_) => 0b10,
-                Cell::DC(_) => 0b11,
-            };
-            hasher.write_u8(value);
-        }
+        self.lattice.iter().fold(FNV_OFFSET_BASIS, |hash, cell| {
+            (hash ^ cell.to_code() as u64).wrapping_mul(FNV_PRIME)
+        })
+    }
 
-        hasher.finish()
+    // packs the lattice four cells to a byte, fitness is not retained
+    pub fn encode_lattice(&self) -> Vec<u8> {
+        self.lattice
+            .chunks(4)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, cell)| byte | (cell.to_code() << (2 * i)))
+            })
+            .collect()
     }
 }
 
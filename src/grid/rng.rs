@@ -1,16 +1,31 @@
-use rand::{Rng, rngs::ThreadRng};
+use rand::{Rng, SeedableRng, rngs::ThreadRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::CrawlError;
+
+// how the initial strategies are drawn from the cooperator frequency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Initialisation {
+    // every cell cooperates independently with the given probability
+    #[default]
+    Independent,
+    // exactly the nearest whole number of cooperators, at shuffled positions
+    ExactCount,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RngSettings {
     pub seed: u64,
     pub cooperator_frequency: f64,
+    #[serde(default)]
+    pub initialisation: Initialisation,
 }
 
 impl RngSettings {
-    pub fn new(seed: Option<u64>, cooperator_frequency: f64) -> Result<Self, String> {
+    pub fn new(seed: Option<u64>, cooperator_frequency: f64) -> Result<Self, CrawlError> {
         if !(0.0..=1.0).contains(&cooperator_frequency) {
-            return Err("cooperator_frequence must lie between 0.0 and 1.0".to_string());
+            return Err(CrawlError::InvalidFrequency(cooperator_frequency));
         }
 
         let seed = seed.unwrap_or_else(|| {
@@ -22,6 +37,28 @@ impl RngSettings {
         Ok(Self {
             seed,
             cooperator_frequency,
+            initialisation: Initialisation::default(),
         })
     }
+
+    // strategy of every cell in lattice order, true for cooperators. chacha8
+    // is named explicitly because `StdRng` may change its algorithm between
+    // rand releases, which would silently change every seeded lattice
+    pub fn initial_strategies(&self, total_cells: usize) -> Vec<bool> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+
+        match self.initialisation {
+            Initialisation::Independent => (0..total_cells)
+                .map(|_| rng.r#gen::<f64>() < self.cooperator_frequency)
+                .collect(),
+            Initialisation::ExactCount => {
+                let cooperators = (self.cooperator_frequency * total_cells as f64).round() as usize;
+
+                let mut strategies: Vec<bool> =
+                    (0..total_cells).map(|index| index < cooperators).collect();
+                strategies.shuffle(&mut rng);
+                strategies
+            }
+        }
+    }
 }
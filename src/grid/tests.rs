@@ -1,8 +1,12 @@
-use super::{Grid, rng::RngSettings};
+use crate::CrawlError;
+
+use super::{
+    Geometry, Grid, Initialisation, MAX_SIDE, cell_count, layered_cell_count, rng::RngSettings,
+};
 
 #[test]
 fn test_get_index_non_wrapped() {
-    let grid = Grid::new((5, 5), false, None);
+    let grid = Grid::new((5, 5), false, None).unwrap();
 
     assert_eq!(
         grid.get_index(0, 0),
@@ -28,7 +32,7 @@ fn test_get_index_non_wrapped() {
 
 #[test]
 fn test_get_index_wrapped() {
-    let grid = Grid::new((5, 5), true, None);
+    let grid = Grid::new((5, 5), true, None).unwrap();
 
     assert_eq!(
         grid.get_index(0, 0),
@@ -54,7 +58,7 @@ fn test_get_index_wrapped() {
 
 #[test]
 fn test_get_cell_non_wrapped() {
-    let grid = Grid::new((5, 5), false, None);
+    let grid = Grid::new((5, 5), false, None).unwrap();
 
     assert!(
         grid.get_cell(2, 3).is_some(),
@@ -76,7 +80,7 @@ fn test_get_cell_non_wrapped() {
 
 #[test]
 fn test_get_cell_wrapped() {
-    let grid = Grid::new((5, 5), false, None);
+    let grid = Grid::new((5, 5), false, None).unwrap();
 
     assert!(
         grid.get_cell(2, 3).is_some(),
@@ -98,7 +102,7 @@ fn test_get_cell_wrapped() {
 
 #[test]
 fn test_get_cell_mut() {
-    let mut grid = Grid::new((5, 5), true, None);
+    let mut grid = Grid::new((5, 5), true, None).unwrap();
 
     let cell = grid.get_cell_mut(2, 3);
     assert!(
@@ -120,8 +124,8 @@ fn test_get_cell_mut() {
 }
 
 #[test]
-fn test_hash_consistency() -> Result<(), String> {
-    let grid = Grid::new((100, 100), true, Some(RngSettings::new(None, 0.5)?));
+fn test_hash_consistency() -> Result<(), CrawlError> {
+    let grid = Grid::new((100, 100), true, Some(RngSettings::new(None, 0.5)?))?;
 
     let hash_1 = grid.get_lattice_hash();
     let hash_2 = grid.get_lattice_hash();
@@ -131,8 +135,8 @@ fn test_hash_consistency() -> Result<(), String> {
 }
 
 #[test]
-fn test_hash_on_change() -> Result<(), String> {
-    let mut grid = Grid::new((100, 100), true, Some(RngSettings::new(None, 0.5)?));
+fn test_hash_on_change() -> Result<(), CrawlError> {
+    let mut grid = Grid::new((100, 100), true, Some(RngSettings::new(None, 0.5)?))?;
 
     let hash_1 = grid.get_lattice_hash();
 
@@ -144,3 +148,330 @@ fn test_hash_on_change() -> Result<(), String> {
     assert_ne!(hash_1, hash_2);
     Ok(())
 }
+
+#[test]
+fn test_get_coordinates() {
+    let grid = Grid::new((4, 5), false, None).unwrap();
+
+    assert_eq!(grid.get_coordinates(0), (0, 0));
+    assert_eq!(grid.get_coordinates(7), (1, 2));
+    assert_eq!(grid.get_coordinates(19), (3, 4));
+}
+
+#[test]
+fn test_resolve() {
+    let wrapped = Grid::new((4, 5), true, None).unwrap();
+    assert_eq!(wrapped.resolve(1, 2), Some((1, 2)));
+    assert_eq!(wrapped.resolve(-1, 5), Some((3, 0)));
+
+    let bounded = Grid::new((4, 5), false, None).unwrap();
+    assert_eq!(bounded.resolve(1, 2), Some((1, 2)));
+    assert_eq!(bounded.resolve(-1, 5), None);
+}
+
+#[test]
+fn test_encode_lattice() {
+    let mut grid = Grid::new((1, 5), false, None).unwrap();
+    grid.get_cell_mut(0, 0).unwrap().update_strategy(false);
+    grid.get_cell_mut(0, 1).unwrap().update_strategy(false);
+    grid.get_cell_mut(0, 1).unwrap().update_strategy(false);
+    grid.get_cell_mut(0, 2).unwrap().update_strategy(true);
+
+    // CD, DD, DC, CC | CC, padded with zeroes
+    assert_eq!(grid.encode_lattice(), vec![0b00_11_10_01, 0b00]);
+}
+
+#[test]
+fn test_from_encoded_roundtrip() -> Result<(), CrawlError> {
+    let grid = Grid::new((7, 9), true, Some(RngSettings::new(Some(5), 0.5)?))?;
+    let restored = Grid::from_encoded((7, 9), true, None, &grid.encode_lattice())?;
+
+    assert_eq!(restored.get_lattice_hash(), grid.get_lattice_hash());
+    assert!(Grid::from_encoded((7, 10), true, None, &grid.encode_lattice()).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_hash_is_pinned() {
+    // recorded histories are compared across processes and machines, so the
+    // hash of a given lattice must never change
+    let grid = Grid::new((5, 5), true, None).unwrap();
+    assert_eq!(grid.get_lattice_hash(), 0xf448_3449_ff04_2b05);
+}
+
+#[test]
+fn test_invalid_frequency() {
+    assert!(matches!(
+        RngSettings::new(Some(1), 1.2),
+        Err(CrawlError::InvalidFrequency(frequency)) if frequency == 1.2
+    ));
+    assert!(RngSettings::new(Some(1), -0.1).is_err());
+    assert!(RngSettings::new(Some(1), 1.0).is_ok());
+}
+
+#[test]
+fn test_cell_count() {
+    assert_eq!(cell_count((3, 4)).unwrap(), 12);
+    assert!(matches!(
+        cell_count((0, 4)),
+        Err(CrawlError::InvalidDimension((0, 4)))
+    ));
+    assert!(matches!(
+        cell_count((5, -1)),
+        Err(CrawlError::InvalidDimension(_))
+    ));
+    assert!(matches!(
+        cell_count((i32::MAX, 2)),
+        Err(CrawlError::DimensionOverflow(_))
+    ));
+
+    // past the point where the cell count overflows i32
+    assert_eq!(cell_count((46_341, 46_341)).unwrap(), 2_147_488_281);
+    assert!(cell_count((MAX_SIDE, 1)).is_ok());
+    assert!(cell_count((MAX_SIDE + 1, 1)).is_err());
+}
+
+#[test]
+fn test_new_rejects_invalid_dimension() {
+    assert!(matches!(
+        Grid::new((0, 5), true, None),
+        Err(CrawlError::InvalidDimension((0, 5)))
+    ));
+    assert!(matches!(
+        Grid::new((-3, 5), false, None),
+        Err(CrawlError::InvalidDimension(_))
+    ));
+    assert_eq!(Grid::new((1, 1), true, None).unwrap().lattice.len(), 1);
+}
+
+#[test]
+fn test_large_lattice_indexing() {
+    // the lattice itself would need tens of gigabytes, indexing only needs
+    // the dimension
+    let grid = Grid {
+        dimension: (50_000, 60_000),
+        layers: 1,
+        wrapped: true,
+        geometry: Geometry::Square,
+        rng_settings: None,
+        lattice: Vec::new(),
+    };
+
+    let last = 50_000 * 60_000 - 1;
+    assert_eq!(grid.get_index(49_999, 59_999), Some(last));
+    assert_eq!(grid.get_index(-1, -1), Some(last));
+    assert_eq!(grid.get_index(50_000, 60_000), Some(0));
+    assert_eq!(grid.get_coordinates(last), (49_999, 59_999));
+    assert_eq!(grid.resolve(-50_001, 60_001), Some((49_999, 1)));
+
+    let bounded = Grid {
+        wrapped: false,
+        ..grid
+    };
+    assert_eq!(bounded.get_index(49_999, 59_999), Some(last));
+    assert_eq!(bounded.get_index(50_000, 0), None);
+    assert_eq!(bounded.get_index(0, -1), None);
+}
+
+#[test]
+fn test_from_encoded_rejects_wrong_length() {
+    assert!(matches!(
+        Grid::from_encoded((4, 4), true, None, &[0; 3]),
+        Err(CrawlError::Parse(_))
+    ));
+    assert!(matches!(
+        Grid::from_encoded((0, 4), true, None, &[]),
+        Err(CrawlError::InvalidDimension(_))
+    ));
+}
+
+fn strategies(grid: &Grid) -> String {
+    grid.lattice
+        .iter()
+        .map(|cell| if cell.is_cooperator() { 'C' } else { 'D' })
+        .collect()
+}
+
+fn seeded_grid(seed: u64, frequency: f64, initialisation: Initialisation) -> Grid {
+    let mut settings = RngSettings::new(Some(seed), frequency).unwrap();
+    settings.initialisation = initialisation;
+    Grid::new((4, 6), true, Some(settings)).unwrap()
+}
+
+#[test]
+fn test_initialisation_is_pinned() {
+    // seeded lattices are part of every recorded trajectory, a change here
+    // breaks replaying them
+    let independent = seeded_grid(42, 0.5, Initialisation::Independent);
+    assert_eq!(strategies(&independent), "DDCDCCCDDCDDDDDCDCCCCCDC");
+    assert_eq!(independent.get_lattice_hash(), 0x87c2_0d52_fa84_fdcd);
+
+    let exact = seeded_grid(42, 0.5, Initialisation::ExactCount);
+    assert_eq!(strategies(&exact), "CDCCCDCDDCDDDDDCDCCCDCDC");
+    assert_eq!(exact.get_lattice_hash(), 0x9b97_0e49_a83f_d60d);
+}
+
+#[test]
+fn test_independent_initialisation_is_random() {
+    let a = seeded_grid(1, 0.5, Initialisation::Independent);
+    let b = seeded_grid(2, 0.5, Initialisation::Independent);
+    assert_ne!(strategies(&a), strategies(&b));
+    assert_eq!(
+        strategies(&a),
+        strategies(&seeded_grid(1, 0.5, Initialisation::Independent))
+    );
+
+    // cells are not all drawn from the same number
+    let mixed = strategies(&a);
+    assert!(mixed.contains('C') && mixed.contains('D'));
+
+    let mut settings = RngSettings::new(Some(7), 0.3).unwrap();
+    settings.initialisation = Initialisation::Independent;
+    let grid = Grid::new((200, 200), true, Some(settings)).unwrap();
+    let fraction = grid
+        .lattice
+        .iter()
+        .filter(|cell| cell.is_cooperator())
+        .count() as f64
+        / grid.lattice.len() as f64;
+    assert!((fraction - 0.3).abs() < 0.01, "fraction {fraction}");
+}
+
+#[test]
+fn test_exact_count_initialisation() {
+    for (frequency, expected) in [(0.0, 0), (0.25, 6), (0.5, 12), (0.9, 22), (1.0, 24)] {
+        for seed in 0..5 {
+            let grid = seeded_grid(seed, frequency, Initialisation::ExactCount);
+            let cooperators = grid
+                .lattice
+                .iter()
+                .filter(|cell| cell.is_cooperator())
+                .count();
+            assert_eq!(cooperators, expected, "frequency {frequency} seed {seed}");
+        }
+    }
+
+    assert_ne!(
+        strategies(&seeded_grid(1, 0.5, Initialisation::ExactCount)),
+        strategies(&seeded_grid(2, 0.5, Initialisation::ExactCount))
+    );
+}
+
+#[test]
+fn test_initialisation_defaults_when_missing() {
+    let settings: RngSettings =
+        serde_json::from_str(r#"{ "seed": 3, "cooperator_frequency": 0.4 }"#).unwrap();
+    assert_eq!(settings.initialisation, Initialisation::Independent);
+}
+
+#[test]
+fn test_with_geometry() {
+    let grid = Grid::new((4, 6), true, None)
+        .unwrap()
+        .with_geometry(Geometry::Triangular)
+        .unwrap();
+    assert_eq!(grid.geometry, Geometry::Triangular);
+
+    // wrapping an odd side would put an up triangle against an up triangle
+    assert!(matches!(
+        Grid::new((5, 6), true, None)
+            .unwrap()
+            .with_geometry(Geometry::Triangular),
+        Err(CrawlError::Config(_))
+    ));
+    assert!(
+        Grid::new((5, 7), false, None)
+            .unwrap()
+            .with_geometry(Geometry::Triangular)
+            .is_ok()
+    );
+    assert!(
+        Grid::new((5, 7), true, None)
+            .unwrap()
+            .with_geometry(Geometry::Hexagonal)
+            .is_ok()
+    );
+
+    assert!(Geometry::points_up(0, 0));
+    assert!(!Geometry::points_up(0, -1));
+    assert!(Geometry::points_up(-1, 1));
+}
+
+#[test]
+fn test_cubic_indexing() {
+    let grid = Grid::cubic((3, 4), 5, true, None).unwrap();
+    assert_eq!(grid.geometry, Geometry::Cubic);
+    assert_eq!(grid.lattice.len(), 60);
+
+    assert_eq!(grid.get_index_3d(0, 0, 0), Some(0));
+    assert_eq!(grid.get_index_3d(1, 2, 3), Some(12 + 8 + 3));
+    assert_eq!(grid.get_index_3d(-1, -1, -1), Some(59));
+    assert_eq!(grid.get_index_3d(5, 3, 4), Some(0));
+    assert_eq!(grid.get_position(23), (1, 2, 3));
+    assert_eq!(grid.get_coordinates(23), (2, 3));
+
+    // the two-dimensional methods address the first layer
+    assert_eq!(grid.get_index(2, 3), grid.get_index_3d(0, 2, 3));
+
+    // the lone defector sits in the middle layer
+    assert_eq!(
+        grid.lattice.iter().position(|cell| !cell.is_cooperator()),
+        Some(30)
+    );
+    assert_eq!(grid.get_position(30), (2, 1, 2));
+
+    let bounded = Grid::cubic((3, 4), 5, false, None).unwrap();
+    assert_eq!(bounded.get_index_3d(4, 2, 3), Some(59));
+    assert_eq!(bounded.get_index_3d(5, 0, 0), None);
+    assert_eq!(bounded.get_index_3d(-1, 0, 0), None);
+}
+
+#[test]
+fn test_wrapped_cubic_needs_three_cells_per_axis() {
+    for (dimension, layers) in [((4, 4), 1), ((4, 4), 2), ((2, 4), 3), ((4, 1), 3)] {
+        assert!(
+            matches!(
+                Grid::cubic(dimension, layers, true, None),
+                Err(CrawlError::Config(_))
+            ),
+            "{layers}x{dimension:?} was accepted"
+        );
+        assert!(Grid::cubic(dimension, layers, false, None).is_ok());
+    }
+    assert!(Grid::cubic((3, 3), 3, true, None).is_ok());
+}
+
+#[test]
+fn test_cubic_hash_covers_every_layer() -> Result<(), CrawlError> {
+    let mut grid = Grid::cubic((3, 3), 3, true, None)?;
+    let hash = grid.get_lattice_hash();
+
+    let index = grid.get_index_3d(2, 1, 1).unwrap();
+    grid.lattice[index].update_strategy(false);
+    assert_ne!(grid.get_lattice_hash(), hash);
+
+    let restored = Grid::from_encoded_layers((3, 3), 3, true, None, &grid.encode_lattice())?;
+    assert_eq!(restored.get_lattice_hash(), grid.get_lattice_hash());
+    Ok(())
+}
+
+#[test]
+fn test_layered_cell_count() {
+    assert_eq!(layered_cell_count((3, 4), 5).unwrap(), 60);
+    assert!(matches!(
+        layered_cell_count((3, 4), 0),
+        Err(CrawlError::Config(_))
+    ));
+    assert!(matches!(
+        layered_cell_count((MAX_SIDE, MAX_SIDE), MAX_SIDE),
+        Err(CrawlError::DimensionOverflow(_))
+    ));
+
+    // only cubic lattices are stacked
+    assert!(matches!(
+        Grid::cubic((3, 3), 3, true, None)
+            .unwrap()
+            .with_geometry(Geometry::Square),
+        Err(CrawlError::Config(_))
+    ));
+}
@@ -1,5 +1,15 @@
-mod cell;
-mod grid;
-mod neighbourhood;
-mod payoff;
-mod trajectory;
+pub mod batch;
+pub mod cell;
+pub mod config;
+pub mod error;
+pub mod grid;
+pub mod neighbourhood;
+pub mod payoff;
+pub mod prelude;
+pub mod render;
+pub mod snapshot;
+pub mod stats;
+pub mod sweep;
+pub mod trajectory;
+
+pub use error::CrawlError;
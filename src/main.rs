@@ -1,3 +1,575 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crawl::{
+    batch::Batch,
+    cell::BuiltinRule,
+    config::{ExperimentConfig, GridConfig},
+    grid::{Geometry, Initialisation, RngSettings},
+    neighbourhood::{CubicShell, Neighbourhood, NeighbourhoodShape},
+    payoff::{Payoff, PayoffMatrix},
+    render::{GifOptions, ImageFormat, Palette, Renderer},
+    snapshot,
+    stats::{self, Statistics},
+    sweep::Sweep,
+    trajectory::{DEFAULT_OUTPUT_ROOT, Schedule, Trajectory},
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "crawl",
+    version,
+    about = "Spatial prisoner's dilemma simulations"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Directory new trajectories are stored under, `trajectories` unless a
+    /// sweep file sets its own
+    #[arg(long, global = true)]
+    output_root: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run a new trajectory
+    Run(RunArgs),
+    /// Run trajectories described by TOML or JSON config files
+    Experiment(ExperimentArgs),
+    /// Run every parameter combination of a TOML or JSON sweep file
+    Sweep(SweepArgs),
+    /// Render the snapshots of a trajectory into numbered frames
+    Render(RenderArgs),
+    /// Summarise a trajectory directory
+    Inspect(InspectArgs),
+    /// Re-run a trajectory from its metadata and verify its recorded history
+    Replay(ReplayArgs),
+}
+
+#[derive(Debug, Args)]
+struct RunArgs {
+    /// Name the trajectory is stored under in `trajectories/`
+    #[arg(long, default_value = "run")]
+    name: String,
+
+    #[arg(long, default_value_t = 100)]
+    rows: i32,
+
+    #[arg(long, default_value_t = 100)]
+    cols: i32,
+
+    /// Stack this many lattices into a cubic lattice, needs --geometry cubic
+    #[arg(long, default_value_t = 1)]
+    layers: i32,
+
+    /// Use fixed boundaries instead of wrapping the lattice into a torus
+    #[arg(long)]
+    bounded: bool,
+
+    #[arg(long, value_enum, default_value_t = GeometryArg::Square)]
+    geometry: GeometryArg,
+
+    /// Defaults to the nearest neighbours of the geometry
+    #[arg(long, value_enum)]
+    neighbourhood: Option<NeighbourhoodArg>,
+
+    /// Neighbourhood radius, a whole number unless the neighbourhood is circular
+    #[arg(long, default_value_t = 1.0)]
+    radius: f64,
+
+    /// Neighbours a cell learns from, defaults to the neighbours it plays against
+    #[arg(long, value_enum)]
+    imitation_neighbourhood: Option<NeighbourhoodArg>,
+
+    #[arg(long, default_value_t = 1.0, requires = "imitation_neighbourhood")]
+    imitation_radius: f64,
+
+    /// Let every cell also play against and imitate itself, as in Nowak and May
+    #[arg(long)]
+    include_self: bool,
+
+    /// Temptation payoff b, paid to a defector meeting a cooperator
+    #[arg(short, long, default_value_t = 1.9)]
+    b: f32,
+
+    /// Reward for mutual cooperation
+    #[arg(long, default_value_t = 1.0)]
+    reward: f32,
+
+    /// Sucker's payoff, paid to a cooperator meeting a defector
+    #[arg(long, default_value_t = 0.0)]
+    sucker: f32,
+
+    /// Punishment for mutual defection
+    #[arg(long, default_value_t = 0.0)]
+    punishment: f32,
+
+    /// Seed for the initial lattice and the dynamics, random if omitted
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Initial fraction of cooperators, a lone defector is placed in the
+    /// centre of the lattice if omitted
+    #[arg(long)]
+    cooperator_frequency: Option<f64>,
+
+    /// Place exactly the nearest whole number of cooperators instead of
+    /// drawing every cell independently
+    #[arg(long, requires = "cooperator_frequency")]
+    exact_count: bool,
+
+    #[arg(short, long, default_value_t = 100)]
+    iterations: usize,
+
+    #[arg(long, value_enum, default_value_t = UpdateRuleArg::ImitateBest)]
+    update_rule: UpdateRuleArg,
+
+    /// Noise of the fermi update rule
+    #[arg(long, default_value_t = 0.1)]
+    temperature: f32,
+
+    #[arg(long, value_enum, default_value_t = ScheduleArg::Synchronous)]
+    schedule: ScheduleArg,
+
+    /// Write a lattice snapshot every this many generations
+    #[arg(long)]
+    snapshot_interval: Option<usize>,
+
+    /// Record an animated gif with a frame every this many generations
+    #[arg(long)]
+    gif_stride: Option<usize>,
+
+    /// Stop as soon as the lattice enters a cycle
+    #[arg(long)]
+    stop_on_cycle: bool,
+
+    /// Report the cycle the lattice enters without stopping
+    #[arg(long)]
+    detect_cycles: bool,
+
+    /// Threads sharing each synchronous generation, results do not depend on
+    /// the count
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+}
+
+#[derive(Debug, Args)]
+struct ExperimentArgs {
+    /// Config files, or the `metadata.json` of earlier trajectories
+    #[arg(required = true)]
+    configs: Vec<PathBuf>,
+
+    /// Trajectories run concurrently, every available core if 0
+    #[arg(short, long, default_value_t = 0)]
+    workers: usize,
+}
+
+#[derive(Debug, Args)]
+struct SweepArgs {
+    /// Sweep file holding a `base` experiment config and the swept values
+    sweep: PathBuf,
+
+    /// Trajectories run concurrently, every available core if 0
+    #[arg(short, long, default_value_t = 0)]
+    workers: usize,
+}
+
+#[derive(Debug, Args)]
+struct RenderArgs {
+    /// Trajectory directory containing `snapshots/`
+    directory: PathBuf,
+
+    /// Directory the frames are written to, defaults to `<directory>/frames`
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = FormatArg::Png)]
+    format: FormatArg,
+
+    /// Side length in pixels of each cell
+    #[arg(long, default_value_t = 4)]
+    cell_size: u32,
+
+    /// Write every layer of a cubic lattice into `frame_<n>/slice_<layer>`
+    /// instead of only the middle layer
+    #[arg(long)]
+    slices: bool,
+}
+
+#[derive(Debug, Args)]
+struct InspectArgs {
+    /// Trajectory directory containing `metadata.json`
+    directory: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct ReplayArgs {
+    /// Trajectory directory containing `metadata.json` and `history.txt`
+    directory: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum NeighbourhoodArg {
+    Moore,
+    VonNeumann,
+    Circular,
+    Hexagonal,
+    Triangular,
+    TriangularVertices,
+    CubicFaces,
+    CubicEdges,
+    CubicCorners,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum GeometryArg {
+    Square,
+    Hexagonal,
+    Triangular,
+    Cubic,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum UpdateRuleArg {
+    ImitateBest,
+    Fermi,
+    ProportionalImitation,
+    DeathBirth,
+    BirthDeath,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ScheduleArg {
+    Synchronous,
+    RandomSequential,
+    RandomPermutation,
+    PoissonClock,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Png,
+    Ppm,
+}
+
 fn main() {
-    println!("Hello, world!");
+    let cli = Cli::parse();
+
+    let output_root = cli.output_root;
+    let result = match cli.command {
+        Command::Run(args) => run(args, output_root),
+        Command::Experiment(args) => experiment(args, output_root),
+        Command::Sweep(args) => sweep(args, output_root),
+        Command::Render(args) => render(args),
+        Command::Inspect(args) => inspect(args),
+        Command::Replay(args) => replay(args),
+    };
+
+    if let Err(error) = result {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: RunArgs, output_root: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let rng_settings = match (args.cooperator_frequency, args.seed) {
+        (Some(frequency), seed) => {
+            let mut rng_settings = RngSettings::new(seed, frequency)?;
+            if args.exact_count {
+                rng_settings.initialisation = Initialisation::ExactCount;
+            }
+            Some(rng_settings)
+        }
+        (None, Some(_)) => {
+            return Err("--seed requires --cooperator-frequency".into());
+        }
+        (None, None) => None,
+    };
+
+    let geometry = match args.geometry {
+        GeometryArg::Square => Geometry::Square,
+        GeometryArg::Hexagonal => Geometry::Hexagonal,
+        GeometryArg::Triangular => Geometry::Triangular,
+        GeometryArg::Cubic => Geometry::Cubic,
+    };
+
+    let neighbourhood = match args.neighbourhood {
+        Some(kind) => neighbourhood_from_args(kind, args.radius, "--radius")?,
+        None => Neighbourhood::nearest(geometry).shape().clone(),
+    };
+    let imitation_neighbourhood = args
+        .imitation_neighbourhood
+        .map(|kind| neighbourhood_from_args(kind, args.imitation_radius, "--imitation-radius"))
+        .transpose()?;
+
+    let update_rule = match args.update_rule {
+        UpdateRuleArg::ImitateBest => BuiltinRule::ImitateBest,
+        UpdateRuleArg::Fermi => BuiltinRule::Fermi {
+            temperature: args.temperature,
+        },
+        UpdateRuleArg::ProportionalImitation => BuiltinRule::ProportionalImitation,
+        UpdateRuleArg::DeathBirth => BuiltinRule::DeathBirth,
+        UpdateRuleArg::BirthDeath => BuiltinRule::BirthDeath,
+    };
+
+    let schedule = match args.schedule {
+        ScheduleArg::Synchronous => Schedule::Synchronous,
+        ScheduleArg::RandomSequential => Schedule::RandomSequential,
+        ScheduleArg::RandomPermutation => Schedule::RandomPermutation,
+        ScheduleArg::PoissonClock => Schedule::PoissonClock,
+    };
+
+    let config = ExperimentConfig {
+        name: args.name,
+        max_iterations: args.iterations,
+        grid: GridConfig {
+            dimension: (args.rows, args.cols),
+            layers: args.layers,
+            wrapped: !args.bounded,
+            geometry,
+            rng_settings,
+        },
+        initial_snapshot: None,
+        neighbourhood,
+        imitation_neighbourhood,
+        include_self: args.include_self,
+        imitation_include_self: None,
+        payoff: Payoff::new(PayoffMatrix::new(
+            args.reward,
+            args.sucker,
+            args.punishment,
+            args.b,
+        )),
+        update_rule,
+        schedule,
+        stop_on_cycle: args.stop_on_cycle,
+        detect_cycles: args.detect_cycles,
+        snapshot_interval: args.snapshot_interval,
+        gif: args.gif_stride.map(|stride| GifOptions {
+            stride,
+            ..GifOptions::default()
+        }),
+    };
+
+    run_config(&config, &output_root_or_default(output_root), args.threads)
+}
+
+// only the shape is built here, its offsets wait until the config has been
+// checked against the lattice
+fn neighbourhood_from_args(
+    kind: NeighbourhoodArg,
+    radius: f64,
+    flag: &str,
+) -> Result<NeighbourhoodShape, Box<dyn std::error::Error>> {
+    let whole_radius = || {
+        if radius >= 0.0 && radius.fract() == 0.0 && radius <= u32::MAX as f64 {
+            Ok(radius as u32)
+        } else {
+            Err(format!(
+                "{flag} {radius} must be a whole number for this neighbourhood"
+            ))
+        }
+    };
+
+    Ok(match kind {
+        NeighbourhoodArg::Moore => NeighbourhoodShape::Moore {
+            radius: whole_radius()?,
+        },
+        NeighbourhoodArg::VonNeumann => NeighbourhoodShape::VonNeumann {
+            radius: whole_radius()?,
+        },
+        NeighbourhoodArg::Circular => NeighbourhoodShape::Circular { radius },
+        NeighbourhoodArg::Hexagonal => NeighbourhoodShape::Hexagonal {
+            radius: whole_radius()?,
+        },
+        NeighbourhoodArg::Triangular => NeighbourhoodShape::Triangular { vertices: false },
+        NeighbourhoodArg::TriangularVertices => NeighbourhoodShape::Triangular { vertices: true },
+        NeighbourhoodArg::CubicFaces => NeighbourhoodShape::Cubic {
+            shell: CubicShell::Faces,
+        },
+        NeighbourhoodArg::CubicEdges => NeighbourhoodShape::Cubic {
+            shell: CubicShell::Edges,
+        },
+        NeighbourhoodArg::CubicCorners => NeighbourhoodShape::Cubic {
+            shell: CubicShell::Corners,
+        },
+    })
+}
+
+fn experiment(
+    args: ExperimentArgs,
+    output_root: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output_root = output_root_or_default(output_root);
+    let configs = args
+        .configs
+        .iter()
+        .map(ExperimentConfig::from_file)
+        .collect::<Result<Vec<_>, _>>()?;
+    if let [config] = configs.as_slice() {
+        return run_config(config, &output_root, 1);
+    }
+
+    let mut failures = 0;
+    let mut batch = batch(args.workers);
+    batch.output_root = output_root;
+    for (config, result) in configs.iter().zip(batch.run(&configs)) {
+        match result {
+            Ok(run) => {
+                println!("{}", run.directory.display());
+                print_statistics(&run.statistics);
+            }
+            Err(error) => {
+                eprintln!("error: {}: {error}", config.name);
+                failures += 1;
+            }
+        }
+    }
+
+    match failures {
+        0 => Ok(()),
+        failures => Err(format!("{failures} of {} trajectories failed", configs.len()).into()),
+    }
+}
+
+fn batch(workers: usize) -> Batch {
+    Batch::new(workers).with_progress(|progress| {
+        eprintln!(
+            "[{}/{}] {} {}",
+            progress.completed,
+            progress.total,
+            progress.name,
+            if progress.succeeded { "done" } else { "failed" }
+        );
+    })
+}
+
+fn output_root_or_default(output_root: Option<PathBuf>) -> PathBuf {
+    output_root.unwrap_or_else(|| PathBuf::from(DEFAULT_OUTPUT_ROOT))
+}
+
+fn run_config(
+    config: &ExperimentConfig,
+    output_root: &Path,
+    threads: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = config.build_in(output_root)?;
+    trajectory.threads = threads;
+    trajectory.run()?;
+
+    println!("{}", trajectory.directory().display());
+    print_statistics(&trajectory.statistics());
+    if let Some(cycle) = trajectory.cycle() {
+        println!(
+            "cycle: period {} entered at generation {}",
+            cycle.period, cycle.transient
+        );
+    }
+
+    Ok(())
+}
+
+fn sweep(args: SweepArgs, output_root: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut sweep = Sweep::from_file(&args.sweep)?;
+    if let Some(output_root) = output_root {
+        sweep.output_root = output_root;
+    }
+    let rows = sweep.run_with(&batch(args.workers))?;
+
+    for row in &rows {
+        println!(
+            "{}: cooperators final {:.4} mean {:.4}",
+            row.point.label(),
+            row.final_statistics.cooperator_fraction,
+            row.mean_cooperator_fraction
+        );
+    }
+    println!(
+        "summary written to {}",
+        sweep.directory().join(crawl::sweep::SUMMARY_FILE).display()
+    );
+
+    Ok(())
+}
+
+fn render(args: RenderArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let output = args.output.unwrap_or_else(|| args.directory.join("frames"));
+    let format = match args.format {
+        FormatArg::Png => ImageFormat::Png,
+        FormatArg::Ppm => ImageFormat::Ppm,
+    };
+
+    let renderer = Renderer::new(args.cell_size, Palette::default());
+    let frames = if args.slices {
+        renderer.render_snapshot_slices(&args.directory, &output, format)?
+    } else {
+        renderer.render_snapshots(&args.directory, &output, format)?
+    };
+
+    println!("rendered {} frames into {}", frames.len(), output.display());
+    Ok(())
+}
+
+fn inspect(args: InspectArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let metadata: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(
+        args.directory.join("metadata.json"),
+    )?)?;
+    println!("{}", serde_json::to_string_pretty(&metadata)?);
+
+    if let Ok(statistics) = stats::read_statistics(&args.directory)
+        && let Some(last) = statistics.last()
+    {
+        println!("generations recorded: {}", statistics.len());
+        print_statistics(last);
+    }
+
+    if let Ok(cycle) = std::fs::read_to_string(args.directory.join("cycle.json")) {
+        let cycle: serde_json::Value = serde_json::from_str(&cycle)?;
+        match &cycle["cycle"] {
+            serde_json::Value::Null => println!("cycle: none detected"),
+            cycle => println!(
+                "cycle: period {} entered at generation {}",
+                cycle["period"], cycle["transient"]
+            ),
+        }
+    }
+
+    if let Ok(generations) = snapshot::snapshot_generations(&args.directory) {
+        println!("snapshots: {generations:?}");
+    }
+
+    Ok(())
+}
+
+fn replay(args: ReplayArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, report) = Trajectory::replay(&args.directory)?;
+
+    println!("replayed into {}", report.replay_directory.display());
+    match report.first_mismatch {
+        None => {
+            println!(
+                "history verified over {} generations",
+                report.generations_compared
+            );
+            Ok(())
+        }
+        Some(generation) => Err(format!("history diverges at generation {generation}").into()),
+    }
+}
+
+fn print_statistics(statistics: &Statistics) {
+    println!(
+        "generation {}: cooperators {:.4} (CC {} CD {} DD {} DC {}), fitness mean {:.4} min {:.4} max {:.4}",
+        statistics.generation,
+        statistics.cooperator_fraction,
+        statistics.cc,
+        statistics.cd,
+        statistics.dd,
+        statistics.dc,
+        statistics.mean_fitness,
+        statistics.min_fitness,
+        statistics.max_fitness
+    );
 }
@@ -1,5 +1,9 @@
+use std::cmp::Ordering;
+
 use serde::{Deserialize, Serialize};
 
+use crate::{CrawlError, grid::Geometry};
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Direction {
     Up,
@@ -23,67 +27,483 @@ const OFFSETS: &[(i32, i32)] = &[
     (-1, -1),
 ];
 
-#[derive(Debug)]
+// which of the 26 surrounding cubes count as neighbours
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CubicShell {
+    // the 6 cubes sharing a face
+    Faces,
+    // the 18 cubes sharing a face or an edge
+    Edges,
+    // all 26 cubes sharing at least a corner
+    Corners,
+}
+
+// how a neighbourhood was chosen, this is what gets stored in configs and
+// `metadata.json` so a run can be rebuilt with the same offsets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NeighbourhoodShape {
+    // every cell within chebyshev distance `radius`
+    Moore {
+        radius: u32,
+    },
+    // every cell within manhattan distance `radius`
+    VonNeumann {
+        radius: u32,
+    },
+    // every cell within euclidean distance `radius`
+    Circular {
+        radius: f64,
+    },
+    // every hexagon within `radius` steps on a hexagonal lattice
+    Hexagonal {
+        radius: u32,
+    },
+    // the three triangles sharing an edge on a triangular lattice, or the
+    // twelve sharing a vertex
+    Triangular {
+        vertices: bool,
+    },
+    // a shell of cubes around each cell of a cubic lattice
+    Cubic {
+        shell: CubicShell,
+    },
+    Offsets(Vec<(i32, i32)>),
+    // a bare list of directions, the format used before radii existed
+    #[serde(untagged)]
+    Directions(Vec<Direction>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Neighbourhood {
-    neighbours: Vec<Direction>,
+    shape: NeighbourhoodShape,
+    include_self: bool,
+    offsets: Vec<(i32, i32)>,
+    // offsets of down triangles, which mirror those of up triangles
+    downward_offsets: Option<Vec<(i32, i32)>>,
+    // layer offset of each entry of `offsets`, all zero off cubic lattices
+    layer_offsets: Vec<i32>,
+    directions: Option<Vec<Direction>>,
 }
 
 impl Direction {
+    const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::TopRight,
+        Direction::Right,
+        Direction::BottomRight,
+        Direction::Down,
+        Direction::BottomLeft,
+        Direction::Left,
+        Direction::TopLeft,
+    ];
+
     #[inline]
-    pub const fn to_offset(&self) -> &'static (i32, i32) {
-        &OFFSETS[*self as usize]
+    pub const fn to_offset(self) -> &'static (i32, i32) {
+        &OFFSETS[self as usize]
+    }
+
+    pub fn from_offset(offset: (i32, i32)) -> Option<Self> {
+        OFFSETS
+            .iter()
+            .position(|&candidate| candidate == offset)
+            .map(|index| Self::ALL[index])
+    }
+}
+
+impl NeighbourhoodShape {
+    // the geometry the shape is defined on, `None` for raw offsets which are
+    // taken as given on any lattice
+    pub fn geometry(&self) -> Option<Geometry> {
+        match self {
+            Self::Moore { .. }
+            | Self::VonNeumann { .. }
+            | Self::Circular { .. }
+            | Self::Directions(_) => Some(Geometry::Square),
+            Self::Hexagonal { .. } => Some(Geometry::Hexagonal),
+            Self::Triangular { .. } => Some(Geometry::Triangular),
+            Self::Cubic { .. } => Some(Geometry::Cubic),
+            Self::Offsets(_) => None,
+        }
+    }
+
+    pub fn check_geometry(&self, geometry: Geometry) -> Result<(), CrawlError> {
+        match self.geometry() {
+            Some(shape_geometry) if shape_geometry != geometry => Err(CrawlError::Config(format!(
+                "a {self:?} neighbourhood does not fit a {geometry:?} lattice"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    // every offset has to land on a distinct cell other than the cell itself:
+    // on a wrapped axis the neighbourhood must fit within the side, and on a
+    // bounded one an offset past the side never lands. checked before any
+    // offsets are built, which a huge radius could not afford
+    pub fn check_dimension(
+        &self,
+        (num_rows, num_cols): (i32, i32),
+        wrapped: bool,
+    ) -> Result<(), CrawlError> {
+        let (reach_x, reach_y) = self.reach();
+        let fits = |reach: u64, side: i32| {
+            if wrapped {
+                2 * reach < side as u64
+            } else {
+                reach < side as u64
+            }
+        };
+
+        if reach_x.max(reach_y) > i32::MAX as u64 {
+            return Err(CrawlError::Config(format!(
+                "a {self:?} neighbourhood reaches beyond the largest lattice"
+            )));
+        }
+        if !fits(reach_x, num_cols) || !fits(reach_y, num_rows) {
+            return Err(CrawlError::Config(format!(
+                "a {self:?} neighbourhood does not fit a {} {num_rows}x{num_cols} lattice",
+                if wrapped { "wrapped" } else { "bounded" }
+            )));
+        }
+
+        Ok(())
+    }
+
+    // the furthest any offset reaches along a row and along a column
+    fn reach(&self) -> (u64, u64) {
+        match self {
+            Self::Moore { radius } | Self::VonNeumann { radius } | Self::Hexagonal { radius } => {
+                (*radius as u64, *radius as u64)
+            }
+            Self::Circular { radius } => {
+                let reach = radius.max(0.0).floor() as u64;
+                (reach, reach)
+            }
+            Self::Triangular { vertices: true } => (2, 1),
+            Self::Triangular { vertices: false } | Self::Cubic { .. } => (1, 1),
+            Self::Offsets(offsets) => offsets.iter().fold((0, 0), |(x, y), &(dx, dy)| {
+                (
+                    x.max(dx.unsigned_abs() as u64),
+                    y.max(dy.unsigned_abs() as u64),
+                )
+            }),
+            Self::Directions(directions) => directions.iter().fold((0, 0), |(x, y), d| {
+                let (dx, dy) = *d.to_offset();
+                (
+                    x.max(dx.unsigned_abs() as u64),
+                    y.max(dy.unsigned_abs() as u64),
+                )
+            }),
+        }
+    }
+
+    // offsets of every cell within its layer, or only of up triangles on a
+    // triangular lattice
+    pub fn offsets(&self) -> Vec<(i32, i32)> {
+        match self {
+            Self::Moore { radius } => {
+                let radius = *radius as i32;
+                shell_offsets(radius, |dx, dy| dx.abs().max(dy.abs()) as i64)
+            }
+            Self::VonNeumann { radius } => {
+                let radius = *radius as i32;
+                shell_offsets(radius, |dx, dy| (dx.abs() + dy.abs()) as i64)
+                    .into_iter()
+                    .filter(|(dx, dy)| dx.abs() + dy.abs() <= radius)
+                    .collect()
+            }
+            Self::Circular { radius } => {
+                // squared distances are compared exactly, only the bound is a float
+                let bound = radius.max(0.0).floor() as i32;
+                let limit = radius * radius;
+                shell_offsets(bound, |dx, dy| (dx * dx + dy * dy) as i64)
+                    .into_iter()
+                    .filter(|(dx, dy)| ((dx * dx + dy * dy) as f64) <= limit)
+                    .collect()
+            }
+            Self::Hexagonal { radius } => {
+                // axial distance, the third cube coordinate is -(dx + dy)
+                let radius = *radius as i32;
+                let distance = |dx: i32, dy: i32| (dx.abs() + dy.abs() + (dx + dy).abs()) / 2;
+                shell_offsets(radius, |dx, dy| distance(dx, dy) as i64)
+                    .into_iter()
+                    .filter(|(dx, dy)| distance(*dx, *dy) <= radius)
+                    .collect()
+            }
+            Self::Triangular { vertices } => {
+                // an up triangle shares its base with the cell below and its
+                // apex with the three cells above
+                let mut offsets = vec![(-1, 0), (1, 0), (0, 1)];
+                if *vertices {
+                    offsets.extend([(-2, 0), (2, 0), (-1, -1), (0, -1), (1, -1)]);
+                    offsets.extend([(-2, 1), (-1, 1), (1, 1), (2, 1)]);
+                }
+                sort_triangular(&mut offsets);
+                offsets
+            }
+            Self::Cubic { shell } => cubic_offsets(*shell)
+                .into_iter()
+                .map(|(dx, dy, _)| (dx, dy))
+                .collect(),
+            Self::Offsets(offsets) => offsets.clone(),
+            Self::Directions(directions) => directions.iter().map(|d| *d.to_offset()).collect(),
+        }
+    }
+
+    // the layer component of each of `offsets`
+    pub fn layer_offsets(&self) -> Vec<i32> {
+        match self {
+            Self::Cubic { shell } => cubic_offsets(*shell)
+                .into_iter()
+                .map(|(_, _, dz)| dz)
+                .collect(),
+            _ => vec![0; self.offsets().len()],
+        }
+    }
+
+    // offsets of down triangles, `None` when every cell shares `offsets`
+    pub fn downward_offsets(&self) -> Option<Vec<(i32, i32)>> {
+        match self {
+            Self::Triangular { .. } => {
+                let mut offsets: Vec<(i32, i32)> = self
+                    .offsets()
+                    .into_iter()
+                    .map(|(dx, dy)| (dx, -dy))
+                    .collect();
+                sort_triangular(&mut offsets);
+                Some(offsets)
+            }
+            _ => None,
+        }
     }
 }
 
+// every offset in the square of the given radius except the centre, ordered
+// by `distance` and then clockwise starting from `Up`. at radius one this is
+// the order of `Direction`, so `moore()` and `von_neumann()` sum payoffs in
+// the same order they always have
+fn shell_offsets(radius: i32, distance: impl Fn(i32, i32) -> i64) -> Vec<(i32, i32)> {
+    let mut offsets: Vec<(i32, i32)> = (-radius..=radius)
+        .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+        .filter(|&offset| offset != (0, 0))
+        .collect();
+
+    offsets.sort_by(|a, b| {
+        distance(a.0, a.1)
+            .cmp(&distance(b.0, b.1))
+            .then_with(|| clockwise(*a, *b))
+    });
+    offsets
+}
+
+// ordered by the number of axes moved along, then layer, then clockwise
+// within the layer
+fn cubic_offsets(shell: CubicShell) -> Vec<(i32, i32, i32)> {
+    let axes = match shell {
+        CubicShell::Faces => 1,
+        CubicShell::Edges => 2,
+        CubicShell::Corners => 3,
+    };
+    let moved = |(dx, dy, dz): (i32, i32, i32)| dx.abs() + dy.abs() + dz.abs();
+
+    let mut offsets: Vec<(i32, i32, i32)> = (-1..=1)
+        .flat_map(|dz| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (dx, dy, dz))))
+        .filter(|&offset| (1..=axes).contains(&moved(offset)))
+        .collect();
+    offsets.sort_by(|a, b| {
+        moved(*a)
+            .cmp(&moved(*b))
+            .then(a.2.cmp(&b.2))
+            .then_with(|| clockwise((a.0, a.1), (b.0, b.1)))
+    });
+    offsets
+}
+
+// triangles are ordered by euclidean distance, then clockwise like the shells
+fn sort_triangular(offsets: &mut [(i32, i32)]) {
+    offsets.sort_by(|a, b| {
+        (a.0 * a.0 + a.1 * a.1)
+            .cmp(&(b.0 * b.0 + b.1 * b.1))
+            .then_with(|| clockwise(*a, *b))
+    });
+}
+
+// orders two offsets by their clockwise angle from `Up`. rows grow downwards,
+// so `Up` is a negative dy
+fn clockwise(a: (i32, i32), b: (i32, i32)) -> Ordering {
+    // 0 for the right half including straight up, 1 for the left half
+    let half = |(dx, dy): (i32, i32)| if dx > 0 || (dx == 0 && dy < 0) { 0 } else { 1 };
+
+    half(a).cmp(&half(b)).then_with(|| {
+        // cross product in screen coordinates, positive when b lies clockwise of a
+        let cross = a.0 as i64 * b.1 as i64 - a.1 as i64 * b.0 as i64;
+        0.cmp(&cross)
+    })
+}
+
 impl Neighbourhood {
+    pub fn from_shape(shape: NeighbourhoodShape) -> Self {
+        let offsets = shape.offsets();
+        // only square lattices step in these directions
+        let directions = match shape.geometry() {
+            Some(Geometry::Square) | None => offsets
+                .iter()
+                .map(|&offset| Direction::from_offset(offset))
+                .collect(),
+            Some(_) => None,
+        };
+
+        Self {
+            downward_offsets: shape.downward_offsets(),
+            layer_offsets: shape.layer_offsets(),
+            offsets,
+            directions,
+            shape,
+            include_self: false,
+        }
+    }
+
+    // adds the zero offset in front of the shape, so every cell also plays
+    // against itself and counts itself among the neighbours it imitates, as
+    // in the original nowak-may model
+    pub fn with_self(mut self, include_self: bool) -> Self {
+        if include_self != self.include_self {
+            for offsets in std::iter::once(&mut self.offsets).chain(&mut self.downward_offsets) {
+                if include_self {
+                    offsets.insert(0, (0, 0));
+                } else {
+                    offsets.remove(0);
+                }
+            }
+            if include_self {
+                self.layer_offsets.insert(0, 0);
+            } else {
+                self.layer_offsets.remove(0);
+            }
+            self.include_self = include_self;
+        }
+        self
+    }
+
     pub fn custom(neighbours: Vec<Direction>) -> Self {
-        Self { neighbours }
+        Self::from_shape(NeighbourhoodShape::Directions(neighbours))
+    }
+
+    pub fn from_offsets(offsets: Vec<(i32, i32)>) -> Self {
+        Self::from_shape(NeighbourhoodShape::Offsets(offsets))
     }
 
     pub fn moore() -> Self {
-        Self {
-            neighbours: vec![
-                Direction::Up,
-                Direction::TopRight,
-                Direction::Right,
-                Direction::BottomRight,
-                Direction::Down,
-                Direction::BottomLeft,
-                Direction::Left,
-                Direction::TopLeft,
-            ],
-        }
+        Self::moore_radius(1)
     }
 
     pub fn von_neumann() -> Self {
-        Self {
-            neighbours: vec![
-                Direction::Up,
-                Direction::Right,
-                Direction::Down,
-                Direction::Left,
-            ],
+        Self::von_neumann_radius(1)
+    }
+
+    pub fn moore_radius(radius: u32) -> Self {
+        Self::from_shape(NeighbourhoodShape::Moore { radius })
+    }
+
+    pub fn von_neumann_radius(radius: u32) -> Self {
+        Self::from_shape(NeighbourhoodShape::VonNeumann { radius })
+    }
+
+    pub fn circular(radius: f64) -> Self {
+        Self::from_shape(NeighbourhoodShape::Circular { radius })
+    }
+
+    pub fn hexagonal() -> Self {
+        Self::hexagonal_radius(1)
+    }
+
+    pub fn hexagonal_radius(radius: u32) -> Self {
+        Self::from_shape(NeighbourhoodShape::Hexagonal { radius })
+    }
+
+    pub fn triangular() -> Self {
+        Self::from_shape(NeighbourhoodShape::Triangular { vertices: false })
+    }
+
+    pub fn triangular_vertices() -> Self {
+        Self::from_shape(NeighbourhoodShape::Triangular { vertices: true })
+    }
+
+    pub fn cubic(shell: CubicShell) -> Self {
+        Self::from_shape(NeighbourhoodShape::Cubic { shell })
+    }
+
+    // nearest neighbours of the given geometry
+    pub fn nearest(geometry: Geometry) -> Self {
+        match geometry {
+            Geometry::Square => Self::moore(),
+            Geometry::Hexagonal => Self::hexagonal(),
+            Geometry::Triangular => Self::triangular(),
+            Geometry::Cubic => Self::cubic(CubicShell::Corners),
         }
     }
 
     #[inline]
-    pub fn get_directions(&self) -> &[Direction] {
-        &self.neighbours
+    pub fn shape(&self) -> &NeighbourhoodShape {
+        &self.shape
+    }
+
+    // the shape's offsets as directions when all of them are single steps on
+    // a square lattice, as for `moore()`, `von_neumann()` and `custom()`. the
+    // cell itself is never among them
+    #[inline]
+    pub fn get_directions(&self) -> Option<&[Direction]> {
+        self.directions.as_deref()
+    }
+
+    #[inline]
+    pub fn includes_self(&self) -> bool {
+        self.include_self
     }
 
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    // offsets of up triangles on a triangular lattice, see `offsets_at`.
+    // these stay within a layer, so a cubic shell's cubes above and below
+    // show up as `(0, 0)` and only `offsets_3d_at` tells them apart
     #[inline]
     pub fn offsets_iter(&self) -> impl Iterator<Item = &(i32, i32)> {
-        self.neighbours.iter().map(|d| d.to_offset())
+        self.offsets.iter()
+    }
+
+    // in-layer offsets of the cell at `(row, col)`
+    #[inline]
+    pub fn offsets_at(&self, row: i32, col: i32) -> &[(i32, i32)] {
+        match &self.downward_offsets {
+            Some(downward_offsets) if !Geometry::points_up(row, col) => downward_offsets,
+            _ => &self.offsets,
+        }
+    }
+
+    // `(dx, dy, dz)` offsets of the cell at `(row, col)` in any layer
+    #[inline]
+    pub fn offsets_3d_at(&self, row: i32, col: i32) -> impl Iterator<Item = (i32, i32, i32)> {
+        self.offsets_at(row, col)
+            .iter()
+            .zip(&self.layer_offsets)
+            .map(|(&(dx, dy), &dz)| (dx, dy, dz))
     }
 }
 
+// the in-layer offsets of `offsets_iter`
 impl<'a> IntoIterator for &'a Neighbourhood {
-    type Item = &'static (i32, i32);
-    type IntoIter =
-        std::iter::Map<std::slice::Iter<'a, Direction>, fn(&Direction) -> &'static (i32, i32)>;
+    type Item = &'a (i32, i32);
+    type IntoIter = std::slice::Iter<'a, (i32, i32)>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.neighbours.iter().map(Direction::to_offset)
+        self.offsets.iter()
     }
 }
 
@@ -1,4 +1,7 @@
+use std::collections::HashSet;
+
 use super::*;
+use crate::grid::Grid;
 
 #[test]
 fn test_direction_to_offset() {
@@ -15,7 +18,7 @@ fn test_direction_to_offset() {
 #[test]
 fn test_moore_neighbourhood() {
     let neighbourhood = Neighbourhood::moore();
-    let expected_directions = vec![
+    let expected_directions = [
         Direction::Up,
         Direction::TopRight,
         Direction::Right,
@@ -26,7 +29,10 @@ fn test_moore_neighbourhood() {
         Direction::TopLeft,
     ];
 
-    assert_eq!(neighbourhood.get_directions(), &expected_directions);
+    assert_eq!(
+        neighbourhood.get_directions(),
+        Some(&expected_directions[..])
+    );
 
     let offsets: Vec<(i32, i32)> = neighbourhood.offsets_iter().cloned().collect();
     let expected_offsets: Vec<(i32, i32)> =
@@ -37,14 +43,17 @@ fn test_moore_neighbourhood() {
 #[test]
 fn test_von_neumann_neighbourhood() {
     let neighbourhood = Neighbourhood::von_neumann();
-    let expected_directions = vec![
+    let expected_directions = [
         Direction::Up,
         Direction::Right,
         Direction::Down,
         Direction::Left,
     ];
 
-    assert_eq!(neighbourhood.get_directions(), &expected_directions);
+    assert_eq!(
+        neighbourhood.get_directions(),
+        Some(&expected_directions[..])
+    );
 
     let offsets: Vec<(i32, i32)> = neighbourhood.offsets_iter().cloned().collect();
     let expected_offsets: Vec<(i32, i32)> =
@@ -56,8 +65,12 @@ fn test_von_neumann_neighbourhood() {
 fn test_custom_neighbourhood() {
     let custom_directions = vec![Direction::Up, Direction::Left, Direction::Down];
     let neighbourhood = Neighbourhood::custom(custom_directions.clone());
+    assert_eq!(
+        neighbourhood.shape(),
+        &NeighbourhoodShape::Directions(custom_directions.clone())
+    );
 
-    assert_eq!(neighbourhood.get_directions(), &custom_directions);
+    assert_eq!(neighbourhood.get_directions(), Some(&custom_directions[..]));
 
     let offsets: Vec<(i32, i32)> = neighbourhood.offsets_iter().cloned().collect();
     let expected_offsets: Vec<(i32, i32)> =
@@ -76,7 +89,7 @@ fn test_offsets_iter_repeated_calls() {
 #[test]
 fn test_moore_neighbourhood_iterator() {
     let neighbourhood = Neighbourhood::moore();
-    let expected_offsets = vec![
+    let expected_offsets = [
         (0, -1),
         (1, -1),
         (1, 0),
@@ -101,3 +114,261 @@ fn test_repeated_iterators() {
     let offsets_second: Vec<&(i32, i32)> = neighbourhood.into_iter().collect();
     assert_eq!(offsets_first, offsets_second);
 }
+
+#[test]
+fn test_radius_one_matches_direction_order() {
+    let moore: Vec<(i32, i32)> = Neighbourhood::moore_radius(1)
+        .offsets_iter()
+        .cloned()
+        .collect();
+    assert_eq!(moore, OFFSETS);
+
+    let von_neumann = Neighbourhood::von_neumann_radius(1);
+    let directions = Neighbourhood::custom(vec![
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ]);
+    assert!(von_neumann.offsets_iter().eq(directions.offsets_iter()));
+}
+
+#[test]
+fn test_radius_neighbour_counts() {
+    assert_eq!(Neighbourhood::moore_radius(0).len(), 0);
+    assert_eq!(Neighbourhood::moore_radius(2).len(), 24);
+    assert_eq!(Neighbourhood::moore_radius(3).len(), 48);
+
+    assert_eq!(Neighbourhood::von_neumann_radius(2).len(), 12);
+    assert_eq!(Neighbourhood::von_neumann_radius(3).len(), 24);
+
+    assert_eq!(Neighbourhood::circular(1.0).len(), 4);
+    assert_eq!(Neighbourhood::circular(1.5).len(), 8);
+    assert_eq!(Neighbourhood::circular(2.0).len(), 12);
+    assert_eq!(Neighbourhood::circular(2.5).len(), 20);
+    assert_eq!(Neighbourhood::circular(-1.0).len(), 0);
+}
+
+#[test]
+fn test_radius_offsets_are_within_radius_and_unique() {
+    let neighbourhood = Neighbourhood::circular(3.2);
+    let offsets: Vec<(i32, i32)> = neighbourhood.into_iter().cloned().collect();
+
+    let mut unique = offsets.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), offsets.len());
+    assert!(!offsets.contains(&(0, 0)));
+    assert!(
+        offsets
+            .iter()
+            .all(|(dx, dy)| ((dx * dx + dy * dy) as f64) <= 3.2 * 3.2)
+    );
+}
+
+#[test]
+fn test_shape_serialisation() -> Result<(), serde_json::Error> {
+    let shape = Neighbourhood::von_neumann_radius(2).shape().clone();
+    let json = serde_json::to_string(&shape)?;
+    assert_eq!(json, r#"{"VonNeumann":{"radius":2}}"#);
+    assert_eq!(serde_json::from_str::<NeighbourhoodShape>(&json)?, shape);
+
+    // configs written before radii existed stored a bare direction list
+    let legacy: NeighbourhoodShape = serde_json::from_str(r#"["Up","Left"]"#)?;
+    assert_eq!(
+        legacy,
+        NeighbourhoodShape::Directions(vec![Direction::Up, Direction::Left])
+    );
+
+    let offsets = NeighbourhoodShape::Offsets(vec![(2, 0), (-2, 0)]);
+    let json = serde_json::to_string(&offsets)?;
+    assert_eq!(serde_json::from_str::<NeighbourhoodShape>(&json)?, offsets);
+    Ok(())
+}
+
+#[test]
+fn test_with_self_adds_zero_offset() {
+    let neighbourhood = Neighbourhood::von_neumann().with_self(true);
+    assert!(neighbourhood.includes_self());
+    assert_eq!(
+        neighbourhood.offsets_iter().cloned().collect::<Vec<_>>(),
+        vec![(0, 0), (0, -1), (1, 0), (0, 1), (-1, 0)]
+    );
+
+    let neighbourhood = neighbourhood.with_self(false);
+    assert!(!neighbourhood.includes_self());
+    assert_eq!(neighbourhood, Neighbourhood::von_neumann());
+}
+
+#[test]
+fn test_hexagonal_neighbour_counts() {
+    assert_eq!(Neighbourhood::hexagonal().len(), 6);
+    assert_eq!(Neighbourhood::hexagonal_radius(2).len(), 18);
+    assert_eq!(Neighbourhood::hexagonal_radius(3).len(), 36);
+
+    // axial neighbours, the two diagonals that are not adjacent are missing
+    let offsets: HashSet<(i32, i32)> = Neighbourhood::hexagonal().offsets_iter().cloned().collect();
+    let expected: HashSet<(i32, i32)> = [(0, -1), (1, -1), (1, 0), (0, 1), (-1, 1), (-1, 0)]
+        .into_iter()
+        .collect();
+    assert_eq!(offsets, expected);
+}
+
+#[test]
+fn test_triangular_offsets_depend_on_orientation() {
+    let neighbourhood = Neighbourhood::triangular();
+    assert_eq!(neighbourhood.offsets_at(0, 0), [(1, 0), (0, 1), (-1, 0)]);
+    assert_eq!(neighbourhood.offsets_at(0, 1), [(0, -1), (1, 0), (-1, 0)]);
+    assert_eq!(
+        neighbourhood.offsets_at(1, 1),
+        neighbourhood.offsets_at(0, 0)
+    );
+
+    let vertices = Neighbourhood::triangular_vertices();
+    assert_eq!(vertices.offsets_at(0, 0).len(), 12);
+    assert_eq!(vertices.offsets_at(0, 1).len(), 12);
+
+    // self-interaction applies to both orientations
+    let neighbourhood = neighbourhood.with_self(true);
+    assert_eq!(neighbourhood.offsets_at(0, 0)[0], (0, 0));
+    assert_eq!(neighbourhood.offsets_at(0, 1)[0], (0, 0));
+}
+
+// every cell of a wrapped lattice has distinct neighbours, and is a neighbour
+// of each of them in turn
+fn assert_symmetric(grid: &Grid, neighbourhood: &Neighbourhood) {
+    let neighbours = |row: i32, col: i32| -> Vec<(i32, i32)> {
+        neighbourhood
+            .offsets_at(row, col)
+            .iter()
+            .filter_map(|(dx, dy)| grid.resolve(row + dy, col + dx))
+            .collect()
+    };
+
+    for (row, col) in grid.coordinates() {
+        let cells = neighbours(row, col);
+        let unique: HashSet<&(i32, i32)> = cells.iter().collect();
+        assert_eq!(unique.len(), neighbourhood.offsets_at(row, col).len());
+        assert!(!unique.contains(&(row, col)));
+
+        for &(other_row, other_col) in &cells {
+            assert!(
+                neighbours(other_row, other_col).contains(&(row, col)),
+                "({row}, {col}) is not a neighbour of ({other_row}, {other_col})"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_wrapped_geometries_are_symmetric() {
+    let hexagonal = Grid::new((5, 7), true, None)
+        .unwrap()
+        .with_geometry(Geometry::Hexagonal)
+        .unwrap();
+    assert_symmetric(&hexagonal, &Neighbourhood::hexagonal());
+    assert_symmetric(&hexagonal, &Neighbourhood::hexagonal_radius(2));
+
+    let triangular = Grid::new((6, 8), true, None)
+        .unwrap()
+        .with_geometry(Geometry::Triangular)
+        .unwrap();
+    assert_symmetric(&triangular, &Neighbourhood::triangular());
+    assert_symmetric(&triangular, &Neighbourhood::triangular_vertices());
+}
+
+#[test]
+fn test_shapes_check_geometry() {
+    assert!(
+        NeighbourhoodShape::Moore { radius: 1 }
+            .check_geometry(Geometry::Square)
+            .is_ok()
+    );
+    assert!(
+        NeighbourhoodShape::Moore { radius: 1 }
+            .check_geometry(Geometry::Hexagonal)
+            .is_err()
+    );
+    assert!(
+        NeighbourhoodShape::Hexagonal { radius: 1 }
+            .check_geometry(Geometry::Triangular)
+            .is_err()
+    );
+    assert!(
+        NeighbourhoodShape::Offsets(vec![(1, 0)])
+            .check_geometry(Geometry::Hexagonal)
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_shapes_check_dimension() {
+    let moore = |radius| NeighbourhoodShape::Moore { radius };
+
+    assert!(moore(1).check_dimension((3, 3), true).is_ok());
+    assert!(moore(2).check_dimension((5, 5), true).is_ok());
+    // offsets would wrap onto each other and onto the cell itself
+    assert!(moore(2).check_dimension((5, 4), true).is_err());
+    assert!(moore(3).check_dimension((3, 3), true).is_err());
+
+    assert!(moore(2).check_dimension((3, 3), false).is_ok());
+    assert!(moore(3).check_dimension((3, 3), false).is_err());
+    assert!(moore(u32::MAX).check_dimension((10, 10), false).is_err());
+
+    let offsets = NeighbourhoodShape::Offsets(vec![(0, 3), (-1, 0)]);
+    assert!(offsets.check_dimension((6, 3), true).is_err());
+    assert!(offsets.check_dimension((7, 3), true).is_ok());
+}
+
+#[test]
+fn test_directions_cover_single_steps() {
+    assert_eq!(Neighbourhood::moore_radius(2).get_directions(), None);
+    assert_eq!(Neighbourhood::hexagonal().get_directions(), None);
+    assert_eq!(
+        Neighbourhood::from_offsets(vec![(0, -1), (1, 0)])
+            .with_self(true)
+            .get_directions(),
+        Some(&[Direction::Up, Direction::Right][..])
+    );
+}
+
+#[test]
+fn test_cubic_shells() {
+    let faces = Neighbourhood::cubic(CubicShell::Faces);
+    let edges = Neighbourhood::cubic(CubicShell::Edges);
+    let corners = Neighbourhood::cubic(CubicShell::Corners);
+    assert_eq!(faces.len(), 6);
+    assert_eq!(edges.len(), 18);
+    assert_eq!(corners.len(), 26);
+    assert_eq!(Neighbourhood::nearest(Geometry::Cubic), corners);
+
+    // the layer below, then the layer itself in direction order, then above
+    let offsets: Vec<(i32, i32, i32)> = faces.offsets_3d_at(0, 0).collect();
+    assert_eq!(
+        offsets,
+        [
+            (0, 0, -1),
+            (0, -1, 0),
+            (1, 0, 0),
+            (0, 1, 0),
+            (-1, 0, 0),
+            (0, 0, 1)
+        ]
+    );
+
+    let unique: HashSet<(i32, i32, i32)> = corners.offsets_3d_at(0, 0).collect();
+    assert_eq!(unique.len(), 26);
+    assert!(!unique.contains(&(0, 0, 0)));
+
+    let with_self = edges.with_self(true);
+    assert_eq!(with_self.offsets_3d_at(0, 0).next(), Some((0, 0, 0)));
+    assert_eq!(with_self.len(), 19);
+
+    assert!(
+        NeighbourhoodShape::Cubic {
+            shell: CubicShell::Faces
+        }
+        .check_geometry(Geometry::Square)
+        .is_err()
+    );
+}
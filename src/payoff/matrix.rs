@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::cell::Cell;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct PayoffMatrix {
     pub c_c: f32,
     pub c_d: f32,
@@ -1,31 +1,37 @@
 use crate::cell::Cell;
 
 mod matrix;
-use matrix::PayoffMatrix;
+mod spatial;
+pub use matrix::PayoffMatrix;
 use serde::{Deserialize, Serialize};
+pub use spatial::SpatialPayoff;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Payoff {
     pub matrix: PayoffMatrix,
-    // add spatial payoff
+    pub spatial: SpatialPayoff,
 }
 
 impl Payoff {
     pub fn new(matrix: PayoffMatrix) -> Self {
-        Self { matrix }
+        Self {
+            matrix,
+            spatial: SpatialPayoff::None,
+        }
     }
 
-    pub fn get_payoff(
-        &self,
-        cell_1: &Cell,
-        cell_2: &Cell,
-        _coordinates: Option<(i32, i32)>,
-    ) -> f32 {
-        let matrix_payoff = self.matrix.get_payoff(cell_1, cell_2);
+    pub fn with_spatial(matrix: PayoffMatrix, spatial: SpatialPayoff) -> Self {
+        Self { matrix, spatial }
+    }
 
-        // add spatial payoff
+    // the spatial term is earned in every game, so a cell's fitness gains it
+    // once per opponent
+    pub fn get_payoff(&self, cell_1: &Cell, cell_2: &Cell, coordinates: Option<(i32, i32)>) -> f32 {
+        let matrix_payoff = self.matrix.get_payoff(cell_1, cell_2);
+        let spatial_payoff =
+            coordinates.map_or(0.0, |coordinates| self.spatial.get_payoff(coordinates));
 
-        matrix_payoff
+        matrix_payoff + spatial_payoff
     }
 }
 
@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::CrawlError;
+
+// a position-dependent term added to the payoff of every game played at a
+// position, see `Payoff::get_payoff`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum SpatialPayoff {
+    #[default]
+    None,
+    Constant {
+        value: f32,
+    },
+    LinearGradient {
+        origin: f32,
+        row_slope: f32,
+        col_slope: f32,
+    },
+    // gaussian bump of height `amplitude` centred on `centre` as (row, col)
+    RadialHotspot {
+        centre: (f32, f32),
+        amplitude: f32,
+        radius: f32,
+    },
+    // one value per cell in lattice order
+    Map {
+        dimension: (i32, i32),
+        values: Vec<f32>,
+    },
+}
+
+impl SpatialPayoff {
+    // reads a map with one lattice row per line, values separated by
+    // whitespace or commas
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CrawlError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(CrawlError::io(path))?;
+
+        let mut values = Vec::new();
+        let mut num_rows = 0;
+        let mut num_cols = None;
+
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let row = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|value| !value.is_empty())
+                .map(|value| {
+                    value.parse::<f32>().map_err(|_| {
+                        CrawlError::Parse(format!(
+                            "{}: invalid spatial payoff {value:?} on row {}",
+                            path.display(),
+                            num_rows + 1
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<f32>, _>>()?;
+
+            match num_cols {
+                None => num_cols = Some(row.len()),
+                Some(num_cols) if num_cols != row.len() => {
+                    return Err(CrawlError::Parse(format!(
+                        "{}: spatial payoff row {} has {} values, expected {}",
+                        path.display(),
+                        num_rows + 1,
+                        row.len(),
+                        num_cols
+                    )));
+                }
+                Some(_) => {}
+            }
+
+            values.extend(row);
+            num_rows += 1;
+        }
+
+        Ok(SpatialPayoff::Map {
+            dimension: (num_rows, num_cols.unwrap_or(0) as i32),
+            values,
+        })
+    }
+
+    // a map must hold one value per cell of a lattice of `dimension`, and a
+    // hotspot needs a positive radius to be finite
+    pub fn check_dimension(&self, dimension: (i32, i32)) -> Result<(), CrawlError> {
+        match self {
+            SpatialPayoff::RadialHotspot { radius, .. }
+                if !(*radius > 0.0 && radius.is_finite()) =>
+            {
+                Err(CrawlError::Config(format!(
+                    "spatial payoff hotspot radius {radius} must be positive and finite"
+                )))
+            }
+            SpatialPayoff::Map {
+                dimension: map_dimension,
+                values,
+            } => {
+                if *map_dimension != dimension {
+                    return Err(CrawlError::Config(format!(
+                        "spatial payoff map is {}x{} but the lattice is {}x{}",
+                        map_dimension.0, map_dimension.1, dimension.0, dimension.1
+                    )));
+                }
+                let cells = dimension.0 as usize * dimension.1 as usize;
+                if values.len() != cells {
+                    return Err(CrawlError::Config(format!(
+                        "spatial payoff map holds {} values, a {}x{} lattice needs {cells}",
+                        values.len(),
+                        dimension.0,
+                        dimension.1
+                    )));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get_payoff(&self, (row, col): (i32, i32)) -> f32 {
+        match self {
+            SpatialPayoff::None => 0.0,
+            SpatialPayoff::Constant { value } => *value,
+            SpatialPayoff::LinearGradient {
+                origin,
+                row_slope,
+                col_slope,
+            } => origin + row_slope * row as f32 + col_slope * col as f32,
+            SpatialPayoff::RadialHotspot {
+                centre,
+                amplitude,
+                radius,
+            } => {
+                let distance_squared =
+                    (row as f32 - centre.0).powi(2) + (col as f32 - centre.1).powi(2);
+                amplitude * (-distance_squared / (2.0 * radius * radius)).exp()
+            }
+            SpatialPayoff::Map { dimension, values } => {
+                let (num_rows, num_cols) = *dimension;
+                if (0..num_rows).contains(&row) && (0..num_cols).contains(&col) {
+                    values[row as usize * num_cols as usize + col as usize]
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
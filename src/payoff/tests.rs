@@ -1,6 +1,6 @@
-use crate::cell::Cell;
+use crate::{CrawlError, cell::Cell};
 
-use super::{Payoff, PayoffMatrix};
+use super::{Payoff, PayoffMatrix, SpatialPayoff};
 
 #[test]
 fn test_matrix_new() {
@@ -36,3 +36,159 @@ fn test_payoff_new() {
     assert_eq!(payoff.get_payoff(&defector, &defector, None), 5.0);
     assert_eq!(payoff.get_payoff(&defector, &cooperator, None), 1.0);
 }
+
+#[test]
+fn test_spatial_none() {
+    assert_eq!(SpatialPayoff::None.get_payoff((3, 4)), 0.0);
+}
+
+#[test]
+fn test_spatial_constant() {
+    let spatial = SpatialPayoff::Constant { value: 0.5 };
+    assert_eq!(spatial.get_payoff((0, 0)), 0.5);
+    assert_eq!(spatial.get_payoff((10, 3)), 0.5);
+}
+
+#[test]
+fn test_spatial_linear_gradient() {
+    let spatial = SpatialPayoff::LinearGradient {
+        origin: 1.0,
+        row_slope: 0.5,
+        col_slope: -0.25,
+    };
+    assert_eq!(spatial.get_payoff((0, 0)), 1.0);
+    assert_eq!(spatial.get_payoff((2, 4)), 1.0);
+    assert_eq!(spatial.get_payoff((4, 0)), 3.0);
+}
+
+#[test]
+fn test_spatial_radial_hotspot() {
+    let spatial = SpatialPayoff::RadialHotspot {
+        centre: (5.0, 5.0),
+        amplitude: 2.0,
+        radius: 1.0,
+    };
+    assert_eq!(spatial.get_payoff((5, 5)), 2.0);
+    assert!((spatial.get_payoff((5, 6)) - 2.0 * (-0.5_f32).exp()).abs() < 1e-6);
+    assert!(spatial.get_payoff((5, 6)) > spatial.get_payoff((6, 6)));
+    assert!(spatial.get_payoff((50, 50)) < 1e-6);
+}
+
+#[test]
+fn test_spatial_map() {
+    let spatial = SpatialPayoff::Map {
+        dimension: (2, 3),
+        values: vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0],
+    };
+    assert_eq!(spatial.get_payoff((0, 2)), 2.0);
+    assert_eq!(spatial.get_payoff((1, 0)), 3.0);
+    assert_eq!(spatial.get_payoff((2, 0)), 0.0, "outside the map");
+    assert_eq!(spatial.get_payoff((0, -1)), 0.0, "outside the map");
+}
+
+#[test]
+fn test_spatial_check_dimension() {
+    let map = SpatialPayoff::Map {
+        dimension: (2, 3),
+        values: vec![0.0; 6],
+    };
+    assert!(map.check_dimension((2, 3)).is_ok());
+    assert!(matches!(
+        map.check_dimension((5, 5)),
+        Err(CrawlError::Config(_))
+    ));
+
+    let short = SpatialPayoff::Map {
+        dimension: (5, 5),
+        values: vec![1.0, 2.0],
+    };
+    assert!(matches!(
+        short.check_dimension((5, 5)),
+        Err(CrawlError::Config(_))
+    ));
+
+    for radius in [0.0, -1.0, f32::NAN] {
+        let hotspot = SpatialPayoff::RadialHotspot {
+            centre: (0.0, 0.0),
+            amplitude: 1.0,
+            radius,
+        };
+        assert!(matches!(
+            hotspot.check_dimension((5, 5)),
+            Err(CrawlError::Config(_))
+        ));
+    }
+    assert!(SpatialPayoff::None.check_dimension((5, 5)).is_ok());
+}
+
+#[test]
+fn test_spatial_from_file() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("crawl_test_spatial_from_file.txt");
+    std::fs::write(&path, "0.0 1.0, 2.0\n\n3.0\t4.0 5.0\n")?;
+
+    let spatial = SpatialPayoff::from_file(&path)?;
+    std::fs::remove_file(&path)?;
+
+    assert_eq!(
+        spatial,
+        SpatialPayoff::Map {
+            dimension: (2, 3),
+            values: vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0],
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_spatial_from_file_ragged() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("crawl_test_spatial_from_file_ragged.txt");
+    std::fs::write(&path, "0.0 1.0\n2.0\n")?;
+
+    let result = SpatialPayoff::from_file(&path);
+    std::fs::remove_file(&path)?;
+
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_payoff_with_spatial() {
+    let payoff = Payoff::with_spatial(
+        PayoffMatrix::new(3.0, 0.0, 5.0, 1.0),
+        SpatialPayoff::LinearGradient {
+            origin: 0.0,
+            row_slope: 1.0,
+            col_slope: 0.0,
+        },
+    );
+
+    let cooperator = Cell::new(true);
+    let defector = Cell::new(false);
+
+    assert_eq!(
+        payoff.get_payoff(&cooperator, &cooperator, Some((2, 0))),
+        5.0
+    );
+    assert_eq!(payoff.get_payoff(&defector, &cooperator, Some((0, 7))), 1.0);
+
+    // without coordinates only the matrix contributes
+    assert_eq!(payoff.get_payoff(&cooperator, &cooperator, None), 3.0);
+}
+
+#[test]
+fn test_payoff_serialises_spatial() -> Result<(), serde_json::Error> {
+    let payoff = Payoff::with_spatial(
+        PayoffMatrix::new(1.0, 0.0, 0.0, 1.9),
+        SpatialPayoff::Constant { value: 0.25 },
+    );
+
+    let value = serde_json::to_value(&payoff)?;
+    assert_eq!(
+        value["spatial"],
+        serde_json::json!({ "Constant": { "value": 0.25 } })
+    );
+
+    let roundtrip: Payoff = serde_json::from_value(value)?;
+    assert_eq!(roundtrip.spatial, payoff.spatial);
+    Ok(())
+}
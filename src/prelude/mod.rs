@@ -0,0 +1,16 @@
+// everything needed to configure, run and inspect trajectories, for
+// `use crawl::prelude::*;`
+pub use crate::{
+    CrawlError,
+    batch::{Batch, BatchRun, Progress},
+    cell::{BuiltinRule, Cell, UpdateRule},
+    config::{ExperimentConfig, GridConfig},
+    grid::{Geometry, Grid, Initialisation, RngSettings},
+    neighbourhood::{CubicShell, Direction, Neighbourhood, NeighbourhoodShape},
+    payoff::{Payoff, PayoffMatrix, SpatialPayoff},
+    render::{GifOptions, GifRecorder, Image, ImageFormat, Palette, Renderer},
+    snapshot::Snapshot,
+    stats::Statistics,
+    sweep::{ParameterValues, Seeds, Sweep, SweepPoint, SweepRow},
+    trajectory::{Cycle, ReplayReport, Schedule, Trajectory, TrajectoryBuilder},
+};
@@ -0,0 +1,133 @@
+use std::{borrow::Cow, fs::File, io::BufWriter, path::Path};
+
+use gif::{Encoder, Frame, Repeat};
+use serde::{Deserialize, Serialize};
+
+use super::{Layout, Renderer};
+use crate::{
+    CrawlError,
+    grid::{Geometry, Grid},
+};
+
+pub const GIF_FILE: &str = "trajectory.gif";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GifOptions {
+    // a frame is recorded every `stride` generations
+    pub stride: usize,
+    // delay between frames in hundredths of a second
+    pub delay: u16,
+    pub renderer: Renderer,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            stride: 1,
+            delay: 10,
+            renderer: Renderer::default(),
+        }
+    }
+}
+
+// index of the black background entry that follows the cell colours in the
+// palette of non-square lattices
+const BACKGROUND: u8 = 4;
+
+// streams frames into an animated gif, the four cell variants index straight
+// into a four colour global palette
+pub struct GifRecorder {
+    encoder: Encoder<BufWriter<File>>,
+    options: GifOptions,
+    layout: Layout,
+    width: u16,
+    height: u16,
+}
+
+impl GifRecorder {
+    pub fn create(
+        path: &Path,
+        dimension: (i32, i32),
+        geometry: Geometry,
+        options: GifOptions,
+    ) -> Result<Self, CrawlError> {
+        // gif frames are at most 65535 pixels on a side
+        let layout = Layout::new(geometry, dimension, options.renderer.cell_size);
+        let (width, height) = layout.size();
+        let width = u16::try_from(width).map_err(|_| CrawlError::DimensionOverflow(dimension))?;
+        let height = u16::try_from(height).map_err(|_| CrawlError::DimensionOverflow(dimension))?;
+
+        let palette = options.renderer.palette;
+        let mut global_palette: Vec<u8> = [palette.cc, palette.cd, palette.dd, palette.dc].concat();
+        if layout.has_background() {
+            global_palette.extend_from_slice(&[0, 0, 0]);
+        }
+
+        let mut encoder = Encoder::new(
+            BufWriter::new(File::create(path).map_err(CrawlError::io(path))?),
+            width,
+            height,
+            &global_palette,
+        )?;
+        encoder.set_repeat(Repeat::Infinite)?;
+
+        Ok(Self {
+            encoder,
+            options,
+            layout,
+            width,
+            height,
+        })
+    }
+
+    #[inline]
+    pub fn options(&self) -> &GifOptions {
+        &self.options
+    }
+
+    // cubic lattices are recorded by their middle layer, as in still images
+    pub fn write_frame(&mut self, grid: &Grid) -> Result<(), CrawlError> {
+        let layer = grid.layers / 2;
+        let mut buffer = Vec::with_capacity(self.width as usize * self.height as usize);
+        for y in 0..self.height as u64 {
+            for x in 0..self.width as u64 {
+                let index = self
+                    .layout
+                    .cell_at(x, y)
+                    .and_then(|(row, col)| grid.get_cell_3d(layer, row, col))
+                    .map_or(BACKGROUND, |cell| cell.to_code());
+                buffer.push(index);
+            }
+        }
+
+        let frame = Frame {
+            delay: self.options.delay,
+            width: self.width,
+            height: self.height,
+            buffer: Cow::Owned(buffer),
+            ..Frame::default()
+        };
+        self.encoder.write_frame(&frame)?;
+
+        Ok(())
+    }
+
+    // writes the trailer, the gif is incomplete until this is called
+    pub fn finish(self) -> Result<(), CrawlError> {
+        self.encoder
+            .into_inner()
+            .map_err(gif::EncodingError::from)?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for GifRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GifRecorder")
+            .field("options", &self.options)
+            .field("layout", &self.layout)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,79 @@
+use crate::grid::Geometry;
+
+// where each cell of a lattice, or of one layer of a cubic lattice, lands in
+// an image, shared by still images and gif frames. hexagonal rows are drawn as
+// bricks shifted half a cell per row, so the image is a rhombus, and triangles
+// are `cell_size` high with bases two cells wide
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Layout {
+    geometry: Geometry,
+    dimension: (i32, i32),
+    cell_size: u64,
+}
+
+impl Layout {
+    pub(crate) fn new(geometry: Geometry, dimension: (i32, i32), cell_size: u32) -> Self {
+        Self {
+            geometry,
+            dimension,
+            cell_size: cell_size.max(1) as u64,
+        }
+    }
+
+    // width and height in pixels
+    pub(crate) fn size(&self) -> (u64, u64) {
+        let (num_rows, num_cols) = (self.dimension.0 as u64, self.dimension.1 as u64);
+        let height = num_rows * self.cell_size;
+
+        let width = match self.geometry {
+            Geometry::Square | Geometry::Cubic => num_cols * self.cell_size,
+            Geometry::Hexagonal => num_cols * self.cell_size + self.row_shift(num_rows - 1),
+            Geometry::Triangular => (num_cols + 1) * self.cell_size,
+        };
+        (width, height)
+    }
+
+    // whether some pixels belong to no cell
+    #[inline]
+    pub(crate) fn has_background(&self) -> bool {
+        matches!(self.geometry, Geometry::Hexagonal | Geometry::Triangular)
+    }
+
+    #[inline]
+    fn row_shift(&self, row: u64) -> u64 {
+        row * self.cell_size / 2
+    }
+
+    // the cell drawn at pixel `(x, y)`, if any
+    pub(crate) fn cell_at(&self, x: u64, y: u64) -> Option<(i32, i32)> {
+        let row = y / self.cell_size;
+        let num_cols = self.dimension.1 as u64;
+
+        let col = match self.geometry {
+            Geometry::Square | Geometry::Cubic => x / self.cell_size,
+            Geometry::Hexagonal => x.checked_sub(self.row_shift(row))? / self.cell_size,
+            Geometry::Triangular => {
+                // both the horizontal position and the depth into the row are
+                // measured in cells, at pixel centres
+                let size = self.cell_size as f64;
+                let u = (x as f64 + 0.5) / size;
+                let t = ((y % self.cell_size) as f64 + 0.5) / size;
+
+                let k = u.floor() as u64;
+                return (k.saturating_sub(1)..=k.min(num_cols.saturating_sub(1)))
+                    .find(|&col| {
+                        let c = col as f64;
+                        let (lo, hi) = if Geometry::points_up(row as i32, col as i32) {
+                            (c + 1.0 - t, c + 1.0 + t)
+                        } else {
+                            (c + t, c + 2.0 - t)
+                        };
+                        (lo..hi).contains(&u)
+                    })
+                    .map(|col| (row as i32, col as i32));
+            }
+        };
+
+        (col < num_cols).then_some((row as i32, col as i32))
+    }
+}
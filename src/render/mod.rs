@@ -0,0 +1,236 @@
+mod animation;
+mod layout;
+
+pub use animation::{GIF_FILE, GifOptions, GifRecorder};
+use layout::Layout;
+
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CrawlError, cell::Cell, grid::Grid, snapshot};
+
+pub type Rgb = [u8; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    pub cc: Rgb,
+    pub cd: Rgb,
+    pub dd: Rgb,
+    pub dc: Rgb,
+}
+
+impl Palette {
+    // colouring from Nowak & May (1992): blue for a C that was C, red for a D
+    // that was D, yellow for a new D and green for a new C
+    pub const fn nowak_may() -> Self {
+        Self {
+            cc: [0, 0, 255],
+            cd: [255, 255, 0],
+            dd: [255, 0, 0],
+            dc: [0, 255, 0],
+        }
+    }
+
+    #[inline]
+    pub fn colour(&self, cell: &Cell) -> Rgb {
+        match cell {
+            Cell::CC(_) => self.cc,
+            Cell::CD(_) => self.cd,
+            Cell::DD(_) => self.dd,
+            Cell::DC(_) => self.dc,
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::nowak_may()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ImageFormat {
+    Ppm,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    // row-major rgb triples
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Renderer {
+    // side length in pixels of the square drawn for each cell, the height
+    // of each hexagon or triangle
+    pub cell_size: u32,
+    pub palette: Palette,
+}
+
+impl Renderer {
+    pub fn new(cell_size: u32, palette: Palette) -> Self {
+        Self {
+            cell_size: cell_size.max(1),
+            palette,
+        }
+    }
+
+    // pixels outside the lattice, around hexagonal and triangular lattices,
+    // are black. cubic lattices are shown by their middle layer
+    pub fn render(&self, grid: &Grid) -> Image {
+        self.render_layer(grid, grid.layers / 2)
+    }
+
+    // a single layer of the lattice, the only one of a planar lattice
+    pub fn render_layer(&self, grid: &Grid, layer: i32) -> Image {
+        let layout = Layout::new(grid.geometry, grid.dimension, self.cell_size);
+        let (width, height) = layout.size();
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let colour = layout
+                    .cell_at(x, y)
+                    .and_then(|(row, col)| grid.get_cell_3d(layer, row, col))
+                    .map_or([0, 0, 0], |cell| self.palette.colour(cell));
+                pixels.extend_from_slice(&colour);
+            }
+        }
+
+        Image {
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        }
+    }
+
+    pub fn write_ppm(&self, grid: &Grid, path: &Path) -> Result<(), CrawlError> {
+        write_ppm_image(&self.render(grid), path)
+    }
+
+    pub fn write_png(&self, grid: &Grid, path: &Path) -> Result<(), CrawlError> {
+        write_png_image(&self.render(grid), path)
+    }
+
+    pub fn write(&self, grid: &Grid, path: &Path, format: ImageFormat) -> Result<(), CrawlError> {
+        write_image(&self.render(grid), path, format)
+    }
+
+    // renders every layer of the lattice as `slice_000.<ext>`, `slice_001.<ext>`,
+    // ... in layer order and returns the paths
+    pub fn write_slices(
+        &self,
+        grid: &Grid,
+        output_directory: &Path,
+        format: ImageFormat,
+    ) -> Result<Vec<PathBuf>, CrawlError> {
+        std::fs::create_dir_all(output_directory).map_err(CrawlError::io(output_directory))?;
+
+        (0..grid.layers)
+            .map(|layer| {
+                let path =
+                    output_directory.join(format!("slice_{layer:03}.{}", format.extension()));
+                write_image(&self.render_layer(grid, layer), &path, format)?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    // renders every snapshot of a trajectory as `frame_000000.<ext>`,
+    // `frame_000001.<ext>`, ... in generation order and returns the paths
+    pub fn render_snapshots(
+        &self,
+        trajectory_directory: &Path,
+        output_directory: &Path,
+        format: ImageFormat,
+    ) -> Result<Vec<PathBuf>, CrawlError> {
+        std::fs::create_dir_all(output_directory).map_err(CrawlError::io(output_directory))?;
+
+        snapshot::snapshot_generations(trajectory_directory)?
+            .into_iter()
+            .enumerate()
+            .map(|(frame, generation)| {
+                let grid = snapshot::load_grid(trajectory_directory, generation)?;
+                let path =
+                    output_directory.join(format!("frame_{frame:06}.{}", format.extension()));
+                self.write(&grid, &path, format)?;
+                Ok(path)
+            })
+            .collect()
+    }
+
+    // like `render_snapshots` but writes every layer of each snapshot, into
+    // `frame_000000/slice_000.<ext>`, ..., and returns the frame directories
+    pub fn render_snapshot_slices(
+        &self,
+        trajectory_directory: &Path,
+        output_directory: &Path,
+        format: ImageFormat,
+    ) -> Result<Vec<PathBuf>, CrawlError> {
+        snapshot::snapshot_generations(trajectory_directory)?
+            .into_iter()
+            .enumerate()
+            .map(|(frame, generation)| {
+                let grid = snapshot::load_grid(trajectory_directory, generation)?;
+                let directory = output_directory.join(format!("frame_{frame:06}"));
+                self.write_slices(&grid, &directory, format)?;
+                Ok(directory)
+            })
+            .collect()
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new(4, Palette::default())
+    }
+}
+
+fn write_ppm_image(image: &Image, path: &Path) -> Result<(), CrawlError> {
+    let mut bytes = format!("P6\n{} {}\n255\n", image.width, image.height).into_bytes();
+    bytes.extend_from_slice(&image.pixels);
+
+    std::fs::write(path, bytes).map_err(CrawlError::io(path))
+}
+
+fn write_png_image(image: &Image, path: &Path) -> Result<(), CrawlError> {
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path).map_err(CrawlError::io(path))?),
+        image.width,
+        image.height,
+    );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.pixels)?;
+
+    Ok(())
+}
+
+fn write_image(image: &Image, path: &Path, format: ImageFormat) -> Result<(), CrawlError> {
+    match format {
+        ImageFormat::Ppm => write_ppm_image(image, path),
+        ImageFormat::Png => write_png_image(image, path),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,270 @@
+use crate::{
+    CrawlError,
+    grid::{Geometry, Grid},
+    snapshot,
+};
+
+use super::*;
+
+fn lone_defector() -> Grid {
+    // 3x3 with the defector in the centre
+    Grid::new((3, 3), true, None).unwrap()
+}
+
+#[test]
+fn test_palette_colour() {
+    let palette = Palette::nowak_may();
+    assert_eq!(palette.colour(&Cell::CC(0.0)), [0, 0, 255]);
+    assert_eq!(palette.colour(&Cell::CD(0.0)), [255, 255, 0]);
+    assert_eq!(palette.colour(&Cell::DD(0.0)), [255, 0, 0]);
+    assert_eq!(palette.colour(&Cell::DC(0.0)), [0, 255, 0]);
+}
+
+#[test]
+fn test_render_cell_size() {
+    let image = Renderer::new(2, Palette::default()).render(&lone_defector());
+
+    assert_eq!((image.width, image.height), (6, 6));
+    assert_eq!(image.pixels.len(), 6 * 6 * 3);
+
+    let pixel = |x: usize, y: usize| &image.pixels[(y * 6 + x) * 3..(y * 6 + x) * 3 + 3];
+    assert_eq!(pixel(0, 0), [0, 0, 255]);
+    assert_eq!(pixel(2, 2), [255, 0, 0]);
+    assert_eq!(pixel(3, 3), [255, 0, 0]);
+    assert_eq!(pixel(4, 3), [0, 0, 255]);
+}
+
+#[test]
+fn test_zero_cell_size_is_clamped() {
+    assert_eq!(Renderer::new(0, Palette::default()).cell_size, 1);
+}
+
+#[test]
+fn test_write_ppm() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("crawl_test_write_ppm.ppm");
+    Renderer::new(1, Palette::default()).write_ppm(&lone_defector(), &path)?;
+
+    let bytes = std::fs::read(&path)?;
+    std::fs::remove_file(&path)?;
+
+    let header = b"P6\n3 3\n255\n";
+    assert_eq!(&bytes[..header.len()], header);
+    assert_eq!(bytes.len(), header.len() + 27);
+    assert_eq!(&bytes[header.len() + 12..header.len() + 15], [255, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn test_write_png() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("crawl_test_write_png.png");
+    let renderer = Renderer::new(3, Palette::default());
+    renderer.write_png(&lone_defector(), &path)?;
+
+    let decoder = png::Decoder::new(File::open(&path)?);
+    let mut reader = decoder.read_info()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels)?;
+    std::fs::remove_file(&path)?;
+
+    assert_eq!((info.width, info.height), (9, 9));
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    assert_eq!(pixels, renderer.render(&lone_defector()).pixels);
+    Ok(())
+}
+
+#[test]
+fn test_render_snapshots() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = std::env::temp_dir().join("crawl_test_render_snapshots");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(
+        directory.join("metadata.json"),
+        r#"{ "grid": { "wrapped": true, "rng_settings": null } }"#,
+    )?;
+
+    let grid = lone_defector();
+    snapshot::write_snapshot(&directory, 0, &grid)?;
+    snapshot::write_snapshot(&directory, 5, &grid)?;
+    snapshot::write_snapshot(&directory, 10, &grid)?;
+
+    let frames = Renderer::default().render_snapshots(
+        &directory,
+        &directory.join("frames"),
+        ImageFormat::Ppm,
+    )?;
+
+    assert_eq!(
+        frames,
+        vec![
+            directory.join("frames").join("frame_000000.ppm"),
+            directory.join("frames").join("frame_000001.ppm"),
+            directory.join("frames").join("frame_000002.ppm"),
+        ]
+    );
+    assert!(frames.iter().all(|frame| frame.exists()));
+
+    std::fs::remove_dir_all(&directory)?;
+    Ok(())
+}
+
+fn count_gif_frames(path: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut decoder = gif::DecodeOptions::new().read_info(File::open(path)?)?;
+    let mut frames = 0;
+    while decoder.read_next_frame()?.is_some() {
+        frames += 1;
+    }
+    Ok(frames)
+}
+
+#[test]
+fn test_gif_recorder() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("crawl_test_gif_recorder.gif");
+    let options = GifOptions {
+        stride: 1,
+        delay: 5,
+        renderer: Renderer::new(2, Palette::default()),
+    };
+
+    let grid = lone_defector();
+    let mut recorder = GifRecorder::create(&path, grid.dimension, grid.geometry, options)?;
+    recorder.write_frame(&grid)?;
+    recorder.write_frame(&grid)?;
+    recorder.finish()?;
+
+    let mut decoder = gif::DecodeOptions::new().read_info(File::open(&path)?)?;
+    assert_eq!((decoder.width(), decoder.height()), (6, 6));
+    assert_eq!(
+        decoder.global_palette().unwrap(),
+        [[0, 0, 255], [255, 255, 0], [255, 0, 0], [0, 255, 0]].concat()
+    );
+
+    let frame = decoder.read_next_frame()?.unwrap();
+    assert_eq!(frame.delay, 5);
+    assert_eq!(frame.buffer[0], Cell::CC(0.0).to_code());
+    assert_eq!(frame.buffer[2 * 6 + 2], Cell::DD(0.0).to_code());
+
+    assert_eq!(count_gif_frames(&path)?, 2);
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn test_gif_recorder_rejects_oversized_lattice() {
+    let path = std::env::temp_dir().join("crawl_test_gif_recorder_rejects_oversized_lattice.gif");
+    let options = GifOptions {
+        renderer: Renderer::new(100, Palette::default()),
+        ..GifOptions::default()
+    };
+
+    assert!(GifRecorder::create(&path, (1000, 10), Geometry::Square, options).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_render_hexagonal() -> Result<(), CrawlError> {
+    let grid = Grid::new((3, 3), true, None)?.with_geometry(Geometry::Hexagonal)?;
+    let image = Renderer::new(2, Palette::default()).render(&grid);
+
+    // each row sits one pixel right of the one above
+    assert_eq!((image.width, image.height), (8, 6));
+    let pixel = |x: usize, y: usize| &image.pixels[(y * 8 + x) * 3..(y * 8 + x) * 3 + 3];
+    assert_eq!(pixel(0, 0), [0, 0, 255]);
+    assert_eq!(pixel(7, 0), [0, 0, 0]);
+    assert_eq!(pixel(0, 2), [0, 0, 0]);
+    assert_eq!(pixel(3, 2), [255, 0, 0]);
+    assert_eq!(pixel(4, 3), [255, 0, 0]);
+    assert_eq!(pixel(7, 5), [0, 0, 255]);
+    Ok(())
+}
+
+#[test]
+fn test_render_triangular() -> Result<(), CrawlError> {
+    let grid = Grid::new((2, 4), true, None)?.with_geometry(Geometry::Triangular)?;
+    let layout = Layout::new(grid.geometry, grid.dimension, 4);
+    assert_eq!(layout.size(), (20, 8));
+
+    // the up triangle at (0, 0) is narrow at its apex and spans its base
+    assert_eq!(layout.cell_at(3, 0), Some((0, 0)));
+    assert_eq!(layout.cell_at(1, 0), None);
+    assert_eq!(layout.cell_at(0, 3), Some((0, 0)));
+    assert_eq!(layout.cell_at(6, 3), Some((0, 0)));
+    // the down triangle beside it is wide at the top
+    assert_eq!(layout.cell_at(4, 0), Some((0, 1)));
+    assert_eq!(layout.cell_at(8, 0), Some((0, 1)));
+    assert_eq!(layout.cell_at(7, 3), Some((0, 1)));
+    // the second row starts with a down triangle
+    assert_eq!(layout.cell_at(1, 4), Some((1, 0)));
+    assert_eq!(layout.cell_at(19, 7), None);
+
+    // the lone defector is the down triangle at (1, 0), every pixel maps to a real cell
+    let image = Renderer::new(4, Palette::default()).render(&grid);
+    let red = image
+        .pixels
+        .chunks(3)
+        .filter(|pixel| *pixel == [255, 0, 0])
+        .count();
+    assert_eq!(red, 16);
+    Ok(())
+}
+
+#[test]
+fn test_gif_recorder_background() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("crawl_test_gif_recorder_background.gif");
+    let options = GifOptions {
+        renderer: Renderer::new(2, Palette::default()),
+        ..GifOptions::default()
+    };
+
+    let grid = Grid::new((3, 3), true, None)?.with_geometry(Geometry::Hexagonal)?;
+    let mut recorder = GifRecorder::create(&path, grid.dimension, grid.geometry, options)?;
+    recorder.write_frame(&grid)?;
+    recorder.finish()?;
+
+    let mut decoder = gif::DecodeOptions::new().read_info(File::open(&path)?)?;
+    assert_eq!((decoder.width(), decoder.height()), (8, 6));
+    assert_eq!(&decoder.global_palette().unwrap()[12..15], [0, 0, 0]);
+
+    let frame = decoder.read_next_frame()?.unwrap();
+    assert_eq!(frame.buffer[7], 4);
+    assert_eq!(frame.buffer[3 * 8 + 4], Cell::DD(0.0).to_code());
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn test_write_slices() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = std::env::temp_dir().join("crawl_test_write_slices");
+    let _ = std::fs::remove_dir_all(&directory);
+
+    // the lone defector sits in the middle layer
+    let grid = Grid::cubic((3, 3), 3, true, None)?;
+    let renderer = Renderer::new(1, Palette::default());
+    let slices = renderer.write_slices(&grid, &directory, ImageFormat::Ppm)?;
+
+    assert_eq!(
+        slices,
+        vec![
+            directory.join("slice_000.ppm"),
+            directory.join("slice_001.ppm"),
+            directory.join("slice_002.ppm"),
+        ]
+    );
+    assert!(slices.iter().all(|slice| slice.exists()));
+
+    let palette = Palette::default();
+    let defectors = |image: &Image| {
+        image
+            .pixels
+            .chunks(3)
+            .filter(|pixel| *pixel == palette.dd)
+            .count()
+    };
+    assert_eq!(defectors(&renderer.render_layer(&grid, 0)), 0);
+    assert_eq!(defectors(&renderer.render_layer(&grid, 1)), 1);
+    assert_eq!(renderer.render(&grid), renderer.render_layer(&grid, 1));
+
+    std::fs::remove_dir_all(&directory)?;
+    Ok(())
+}
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    CrawlError,
+    grid::{Geometry, Grid, RngSettings},
+};
+
+// file layout, all integers little endian:
+//   magic     4 bytes  b"CRWL"
+//   version   u8
+//   rows      u32
+//   cols      u32
+//   layers    u32, absent in version 1 which only held single layers
+//   generation u64
+//   lattice   `Grid::encode_lattice`, four cells per byte
+const MAGIC: &[u8; 4] = b"CRWL";
+const VERSION: u8 = 2;
+const HEADER_LEN: usize = 4 + 1 + 4 + 4 + 4 + 8;
+const HEADER_LEN_V1: usize = 4 + 1 + 4 + 4 + 8;
+
+pub const SNAPSHOT_DIRECTORY: &str = "snapshots";
+// generation zero of a trajectory whose rng settings do not generate it,
+// beside its metadata
+pub const INITIAL_SNAPSHOT_FILE: &str = "initial.bin";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub generation: usize,
+    pub dimension: (i32, i32),
+    pub layers: i32,
+    pub encoded_lattice: Vec<u8>,
+}
+
+impl Snapshot {
+    pub fn from_grid(generation: usize, grid: &Grid) -> Self {
+        Self {
+            generation,
+            dimension: grid.dimension,
+            layers: grid.layers,
+            encoded_lattice: grid.encode_lattice(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.encoded_lattice.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(self.dimension.0 as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.dimension.1 as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.layers as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.generation as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.encoded_lattice);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CrawlError> {
+        if bytes.len() < HEADER_LEN_V1 || &bytes[0..4] != MAGIC {
+            return Err(CrawlError::Parse("not a crawl snapshot".to_string()));
+        }
+        let header_len = match bytes[4] {
+            1 => HEADER_LEN_V1,
+            VERSION => HEADER_LEN,
+            version => {
+                return Err(CrawlError::Parse(format!(
+                    "unsupported snapshot version {version}"
+                )));
+            }
+        };
+        if bytes.len() < header_len {
+            return Err(CrawlError::Parse("not a crawl snapshot".to_string()));
+        }
+
+        let read_u32 =
+            |start: usize| u32::from_le_bytes(bytes[start..start + 4].try_into().unwrap());
+        let rows = read_u32(5);
+        let cols = read_u32(9);
+        let layers = if header_len == HEADER_LEN {
+            read_u32(13)
+        } else {
+            1
+        };
+        let generation = u64::from_le_bytes(bytes[header_len - 8..header_len].try_into().unwrap());
+
+        let (dimension, layers) = match (
+            i32::try_from(rows),
+            i32::try_from(cols),
+            i32::try_from(layers),
+        ) {
+            (Ok(rows), Ok(cols), Ok(layers)) => ((rows, cols), layers),
+            _ => {
+                return Err(CrawlError::Parse(format!(
+                    "snapshot dimension {layers}x{rows}x{cols} is out of range"
+                )));
+            }
+        };
+
+        Ok(Self {
+            generation: generation as usize,
+            dimension,
+            layers,
+            encoded_lattice: bytes[header_len..].to_vec(),
+        })
+    }
+
+    pub fn into_grid(
+        self,
+        wrapped: bool,
+        rng_settings: Option<RngSettings>,
+    ) -> Result<Grid, CrawlError> {
+        Grid::from_encoded_layers(
+            self.dimension,
+            self.layers,
+            wrapped,
+            rng_settings,
+            &self.encoded_lattice,
+        )
+    }
+}
+
+pub fn snapshot_path(trajectory_directory: &Path, generation: usize) -> PathBuf {
+    trajectory_directory
+        .join(SNAPSHOT_DIRECTORY)
+        .join(format!("{generation:08}.bin"))
+}
+
+pub fn write_snapshot(
+    trajectory_directory: &Path,
+    generation: usize,
+    grid: &Grid,
+) -> Result<(), CrawlError> {
+    let snapshot_directory = trajectory_directory.join(SNAPSHOT_DIRECTORY);
+    std::fs::create_dir_all(&snapshot_directory).map_err(CrawlError::io(snapshot_directory))?;
+
+    let path = snapshot_path(trajectory_directory, generation);
+    std::fs::write(&path, Snapshot::from_grid(generation, grid).to_bytes())
+        .map_err(CrawlError::io(path))
+}
+
+pub fn read_snapshot(
+    trajectory_directory: &Path,
+    generation: usize,
+) -> Result<Snapshot, CrawlError> {
+    read_snapshot_file(&snapshot_path(trajectory_directory, generation))
+}
+
+pub fn read_snapshot_file(path: &Path) -> Result<Snapshot, CrawlError> {
+    let bytes = std::fs::read(path).map_err(CrawlError::io(path))?;
+
+    Snapshot::from_bytes(&bytes)
+}
+
+// loads generation `generation` of a trajectory back into a grid, taking the
+// wrapping, geometry and rng settings from the trajectory's metadata
+pub fn load_grid(trajectory_directory: &Path, generation: usize) -> Result<Grid, CrawlError> {
+    let metadata_path = trajectory_directory.join("metadata.json");
+    let metadata: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(&metadata_path).map_err(CrawlError::io(&metadata_path))?,
+    )?;
+
+    let wrapped = metadata["grid"]["wrapped"]
+        .as_bool()
+        .ok_or_else(|| CrawlError::Parse("metadata is missing grid.wrapped".to_string()))?;
+    let rng_settings: Option<RngSettings> =
+        serde_json::from_value(metadata["grid"]["rng_settings"].clone())?;
+    // metadata written before other geometries existed has no geometry
+    let geometry: Geometry = match &metadata["grid"]["geometry"] {
+        serde_json::Value::Null => Geometry::default(),
+        geometry => serde_json::from_value(geometry.clone())?,
+    };
+
+    read_snapshot(trajectory_directory, generation)?
+        .into_grid(wrapped, rng_settings)?
+        .with_geometry(geometry)
+}
+
+// generations with a snapshot on disk, in ascending order
+pub fn snapshot_generations(trajectory_directory: &Path) -> Result<Vec<usize>, CrawlError> {
+    let snapshot_directory = trajectory_directory.join(SNAPSHOT_DIRECTORY);
+    let mut generations: Vec<usize> = std::fs::read_dir(&snapshot_directory)
+        .map_err(CrawlError::io(&snapshot_directory))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()?
+                .strip_suffix(".bin")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    generations.sort_unstable();
+
+    Ok(generations)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,123 @@
+use crate::{
+    cell::Cell,
+    grid::{Grid, RngSettings},
+};
+
+use super::*;
+
+fn mixed_grid() -> Grid {
+    let mut grid = Grid::new((3, 5), true, None).unwrap();
+    grid.get_cell_mut(0, 0).unwrap().update_strategy(false);
+    grid.get_cell_mut(1, 1).unwrap().update_strategy(false);
+    grid.get_cell_mut(1, 1).unwrap().update_strategy(false);
+    grid.get_cell_mut(1, 2).unwrap().update_strategy(true);
+    grid
+}
+
+#[test]
+fn test_snapshot_bytes_roundtrip() {
+    let snapshot = Snapshot::from_grid(12, &mixed_grid());
+    let bytes = snapshot.to_bytes();
+
+    assert_eq!(&bytes[0..4], b"CRWL");
+    assert_eq!(bytes.len(), HEADER_LEN + 4);
+    assert_eq!(Snapshot::from_bytes(&bytes).unwrap(), snapshot);
+}
+
+#[test]
+fn test_snapshot_rejects_invalid_bytes() {
+    assert!(matches!(
+        Snapshot::from_bytes(b"CRWL"),
+        Err(CrawlError::Parse(_))
+    ));
+    assert!(matches!(
+        Snapshot::from_bytes(&[0; HEADER_LEN]),
+        Err(CrawlError::Parse(_))
+    ));
+
+    let mut bytes = Snapshot::from_grid(0, &mixed_grid()).to_bytes();
+    bytes[4] = VERSION + 1;
+    assert!(matches!(
+        Snapshot::from_bytes(&bytes),
+        Err(CrawlError::Parse(_))
+    ));
+}
+
+#[test]
+fn test_snapshot_into_grid() {
+    let grid = mixed_grid();
+    let restored = Snapshot::from_grid(0, &grid).into_grid(true, None).unwrap();
+
+    assert_eq!(restored.dimension, grid.dimension);
+    assert_eq!(restored.get_lattice_hash(), grid.get_lattice_hash());
+    assert_eq!(restored.get_cell(0, 0), Some(&Cell::CD(0.0)));
+    assert_eq!(restored.get_cell(1, 1), Some(&Cell::DD(0.0)));
+    assert_eq!(restored.get_cell(1, 2), Some(&Cell::DC(0.0)));
+    assert_eq!(restored.get_cell(2, 4), Some(&Cell::CC(0.0)));
+}
+
+#[test]
+fn test_snapshot_truncated_lattice() {
+    let mut snapshot = Snapshot::from_grid(0, &mixed_grid());
+    snapshot.encoded_lattice.pop();
+    assert!(snapshot.into_grid(true, None).is_err());
+}
+
+#[test]
+fn test_write_and_load_snapshots() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = std::env::temp_dir().join("crawl_test_write_and_load_snapshots");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory)?;
+
+    let rng_settings = RngSettings::new(Some(3), 0.5)?;
+    std::fs::write(
+        directory.join("metadata.json"),
+        serde_json::json!({ "grid": { "wrapped": false, "rng_settings": rng_settings } })
+            .to_string(),
+    )?;
+
+    let grid = mixed_grid();
+    write_snapshot(&directory, 10, &grid)?;
+    write_snapshot(&directory, 2, &grid)?;
+
+    assert_eq!(snapshot_generations(&directory)?, vec![2, 10]);
+    assert_eq!(read_snapshot(&directory, 10)?.generation, 10);
+
+    let loaded = load_grid(&directory, 2)?;
+    assert!(!loaded.wrapped);
+    assert_eq!(loaded.rng_settings.as_ref().unwrap().seed, 3);
+    assert_eq!(loaded.get_lattice_hash(), grid.get_lattice_hash());
+
+    assert!(read_snapshot(&directory, 3).is_err());
+
+    std::fs::remove_dir_all(&directory)?;
+    Ok(())
+}
+
+#[test]
+fn test_cubic_snapshot_roundtrip() -> Result<(), CrawlError> {
+    let grid = Grid::cubic((3, 4), 3, true, None)?;
+    let snapshot = Snapshot::from_grid(7, &grid);
+    assert_eq!(snapshot.layers, 3);
+
+    let restored = Snapshot::from_bytes(&snapshot.to_bytes())?;
+    assert_eq!(restored, snapshot);
+
+    let restored = restored.into_grid(true, None)?;
+    assert_eq!(restored.layers, 3);
+    assert_eq!(restored.get_lattice_hash(), grid.get_lattice_hash());
+    Ok(())
+}
+
+#[test]
+fn test_version_one_snapshot_is_readable() {
+    let snapshot = Snapshot::from_grid(12, &mixed_grid());
+
+    // version 1 had no layer count
+    let bytes = snapshot.to_bytes();
+    let mut legacy = bytes[..13].to_vec();
+    legacy[4] = 1;
+    legacy.extend_from_slice(&bytes[17..]);
+
+    assert_eq!(Snapshot::from_bytes(&legacy).unwrap(), snapshot);
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{CrawlError, cell::Cell, grid::Grid};
+
+pub const STATISTICS_FILE: &str = "statistics.csv";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Statistics {
+    pub generation: usize,
+    pub cooperator_fraction: f64,
+    pub cc: usize,
+    pub cd: usize,
+    pub dd: usize,
+    pub dc: usize,
+    // fitness is what each cell earned in the games that produced this
+    // generation's strategies
+    pub mean_fitness: f32,
+    pub min_fitness: f32,
+    pub max_fitness: f32,
+    pub strategy_changes: usize,
+}
+
+impl Statistics {
+    pub fn from_grid(generation: usize, grid: &Grid) -> Self {
+        let (mut cc, mut cd, mut dd, mut dc) = (0, 0, 0, 0);
+        let mut total_fitness = 0.0_f64;
+        let mut min_fitness = f32::INFINITY;
+        let mut max_fitness = f32::NEG_INFINITY;
+
+        for cell in &grid.lattice {
+            match cell {
+                Cell::CC(_) => cc += 1,
+                Cell::CD(_) => cd += 1,
+                Cell::DD(_) => dd += 1,
+                Cell::DC(_) => dc += 1,
+            }
+
+            let fitness = cell.get_fitness();
+            total_fitness += fitness as f64;
+            min_fitness = min_fitness.min(fitness);
+            max_fitness = max_fitness.max(fitness);
+        }
+
+        let total_cells = grid.lattice.len();
+        let (cooperator_fraction, mean_fitness) = if total_cells == 0 {
+            (0.0, 0.0)
+        } else {
+            (
+                (cc + dc) as f64 / total_cells as f64,
+                (total_fitness / total_cells as f64) as f32,
+            )
+        };
+
+        Self {
+            generation,
+            cooperator_fraction,
+            cc,
+            cd,
+            dd,
+            dc,
+            mean_fitness,
+            min_fitness: if total_cells == 0 { 0.0 } else { min_fitness },
+            max_fitness: if total_cells == 0 { 0.0 } else { max_fitness },
+            strategy_changes: cd + dc,
+        }
+    }
+
+    pub fn csv_header() -> &'static str {
+        "generation,cooperator_fraction,cc,cd,dd,dc,mean_fitness,min_fitness,max_fitness,strategy_changes"
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.generation,
+            self.cooperator_fraction,
+            self.cc,
+            self.cd,
+            self.dd,
+            self.dc,
+            self.mean_fitness,
+            self.min_fitness,
+            self.max_fitness,
+            self.strategy_changes
+        )
+    }
+
+    pub fn from_csv_row(row: &str) -> Result<Self, CrawlError> {
+        let fields: Vec<&str> = row.trim().split(',').collect();
+        if fields.len() != 10 {
+            return Err(CrawlError::Parse(format!(
+                "expected 10 statistics columns, found {}",
+                fields.len()
+            )));
+        }
+
+        Ok(Self {
+            generation: parse_field(fields[0])?,
+            cooperator_fraction: parse_field(fields[1])?,
+            cc: parse_field(fields[2])?,
+            cd: parse_field(fields[3])?,
+            dd: parse_field(fields[4])?,
+            dc: parse_field(fields[5])?,
+            mean_fitness: parse_field(fields[6])?,
+            min_fitness: parse_field(fields[7])?,
+            max_fitness: parse_field(fields[8])?,
+            strategy_changes: parse_field(fields[9])?,
+        })
+    }
+}
+
+// reads every row of a trajectory's statistics file
+pub fn read_statistics(
+    trajectory_directory: &std::path::Path,
+) -> Result<Vec<Statistics>, CrawlError> {
+    let path = trajectory_directory.join(STATISTICS_FILE);
+    std::fs::read_to_string(&path)
+        .map_err(CrawlError::io(path))?
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(Statistics::from_csv_row)
+        .collect()
+}
+
+fn parse_field<T: std::str::FromStr>(field: &str) -> Result<T, CrawlError> {
+    field
+        .parse()
+        .map_err(|_| CrawlError::Parse(format!("invalid statistics field {field:?}")))
+}
+
+#[cfg(test)]
+mod tests;
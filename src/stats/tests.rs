@@ -0,0 +1,41 @@
+use crate::grid::{Grid, RngSettings};
+
+use super::*;
+
+#[test]
+fn test_statistics_from_grid() {
+    let mut grid = Grid::new((2, 3), true, None).unwrap();
+    grid.get_cell_mut(0, 0).unwrap().update_strategy(false);
+    grid.get_cell_mut(1, 0).unwrap().update_strategy(true);
+    for (i, cell) in grid.lattice.iter_mut().enumerate() {
+        cell.set_fitness(i as f32);
+    }
+
+    let statistics = Statistics::from_grid(4, &grid);
+    assert_eq!(statistics.generation, 4);
+    assert_eq!(
+        (statistics.cc, statistics.cd, statistics.dd, statistics.dc),
+        (4, 1, 0, 1)
+    );
+    assert_eq!(statistics.cooperator_fraction, 5.0 / 6.0);
+    assert_eq!(statistics.strategy_changes, 2);
+    assert_eq!(statistics.mean_fitness, 2.5);
+    assert_eq!(statistics.min_fitness, 0.0);
+    assert_eq!(statistics.max_fitness, 5.0);
+}
+
+#[test]
+fn test_statistics_csv_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let grid = Grid::new((10, 10), true, Some(RngSettings::new(Some(1), 0.5)?))?;
+    let statistics = Statistics::from_grid(7, &grid);
+
+    let row = statistics.to_csv_row();
+    assert_eq!(
+        row.split(',').count(),
+        Statistics::csv_header().split(',').count()
+    );
+    assert_eq!(Statistics::from_csv_row(&row)?, statistics);
+
+    assert!(Statistics::from_csv_row("1,2,3").is_err());
+    Ok(())
+}
@@ -0,0 +1,341 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CrawlError,
+    batch::Batch,
+    config::ExperimentConfig,
+    grid::RngSettings,
+    stats::{self, Statistics},
+    trajectory::DEFAULT_OUTPUT_ROOT,
+};
+
+pub const SUMMARY_FILE: &str = "summary.csv";
+
+// either an explicit list of values or an inclusive range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParameterValues {
+    List(Vec<f64>),
+    Range { start: f64, end: f64, step: f64 },
+}
+
+impl ParameterValues {
+    pub fn values(&self) -> Result<Vec<f64>, CrawlError> {
+        match self {
+            ParameterValues::List(values) => Ok(values.clone()),
+            ParameterValues::Range { start, end, step } => {
+                if *step <= 0.0 || end < start {
+                    return Err(CrawlError::Config(format!(
+                        "invalid range {start}..={end} with step {step}"
+                    )));
+                }
+
+                // rounding keeps `end` itself despite accumulated float error
+                let count = ((end - start) / step + 1e-9).floor() as usize + 1;
+                Ok((0..count).map(|i| start + i as f64 * step).collect())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Seeds {
+    List(Vec<u64>),
+    Count {
+        count: u64,
+        #[serde(default)]
+        start: u64,
+    },
+}
+
+impl Seeds {
+    pub fn values(&self) -> Vec<u64> {
+        match self {
+            Seeds::List(seeds) => seeds.clone(),
+            Seeds::Count { count, start } => (*start..start + count).collect(),
+        }
+    }
+}
+
+// one combination of swept parameters
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SweepPoint {
+    pub c_c: f32,
+    pub c_d: f32,
+    pub d_d: f32,
+    pub d_c: f32,
+    pub cooperator_frequency: Option<f64>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepRow {
+    pub point: SweepPoint,
+    pub directory: PathBuf,
+    pub generations: usize,
+    pub final_statistics: Statistics,
+    // averages over the last `averaging_window` generations
+    pub mean_cooperator_fraction: f64,
+    pub mean_fitness: f64,
+}
+
+// runs every combination of the listed parameters as its own trajectory under
+// `base.name`, unlisted parameters keep their value from `base`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sweep {
+    pub base: ExperimentConfig,
+    #[serde(default)]
+    pub c_c: Option<ParameterValues>,
+    #[serde(default)]
+    pub c_d: Option<ParameterValues>,
+    #[serde(default)]
+    pub d_d: Option<ParameterValues>,
+    #[serde(default)]
+    pub d_c: Option<ParameterValues>,
+    #[serde(default)]
+    pub cooperator_frequency: Option<ParameterValues>,
+    #[serde(default)]
+    pub seeds: Option<Seeds>,
+    // generations at the end of each run that are time-averaged, every
+    // generation after the initial lattice if unset
+    #[serde(default)]
+    pub averaging_window: Option<usize>,
+    #[serde(default = "default_output_root")]
+    pub output_root: PathBuf,
+}
+
+fn default_output_root() -> PathBuf {
+    PathBuf::from(DEFAULT_OUTPUT_ROOT)
+}
+
+impl Sweep {
+    pub fn new(base: ExperimentConfig) -> Self {
+        Self {
+            base,
+            c_c: None,
+            c_d: None,
+            d_d: None,
+            d_c: None,
+            cooperator_frequency: None,
+            seeds: None,
+            averaging_window: None,
+            output_root: default_output_root(),
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CrawlError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(CrawlError::io(path))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Ok(toml::from_str(&contents)?),
+            Some("json") => Ok(serde_json::from_str(&contents)?),
+            _ => Err(CrawlError::Config(format!(
+                "cannot infer sweep format of {}, expected .toml or .json",
+                path.display()
+            ))),
+        }
+    }
+
+    #[inline]
+    pub fn directory(&self) -> PathBuf {
+        self.output_root.join(&self.base.name)
+    }
+
+    // the cartesian product of all swept parameters with the config for each.
+    // unseeded random lattices get a fresh seed on every call
+    pub fn points(&self) -> Result<Vec<(SweepPoint, ExperimentConfig)>, CrawlError> {
+        let matrix = self.base.payoff.matrix;
+        let payoff_values =
+            |values: &Option<ParameterValues>, default: f32| -> Result<Vec<f32>, CrawlError> {
+                match values {
+                    Some(values) => Ok(values.values()?.into_iter().map(|v| v as f32).collect()),
+                    None => Ok(vec![default]),
+                }
+            };
+
+        let c_cs = payoff_values(&self.c_c, matrix.c_c)?;
+        let c_ds = payoff_values(&self.c_d, matrix.c_d)?;
+        let d_ds = payoff_values(&self.d_d, matrix.d_d)?;
+        let d_cs = payoff_values(&self.d_c, matrix.d_c)?;
+
+        let base_rng = self.base.grid.rng_settings.as_ref();
+        let frequencies: Vec<Option<f64>> = match &self.cooperator_frequency {
+            Some(values) => values.values()?.into_iter().map(Some).collect(),
+            None => vec![base_rng.map(|rng| rng.cooperator_frequency)],
+        };
+        let seeds: Vec<Option<u64>> = match &self.seeds {
+            Some(seeds) => seeds.values().into_iter().map(Some).collect(),
+            None => vec![base_rng.map(|rng| rng.seed)],
+        };
+
+        let mut points = Vec::new();
+        for &c_c in &c_cs {
+            for &c_d in &c_ds {
+                for &d_d in &d_ds {
+                    for &d_c in &d_cs {
+                        for &cooperator_frequency in &frequencies {
+                            for &seed in &seeds {
+                                // a random lattice without a seed draws one
+                                // here so that the point records it
+                                let seed = match (cooperator_frequency, seed) {
+                                    (Some(_), None) => Some(rand::random()),
+                                    _ => seed,
+                                };
+                                let point = SweepPoint {
+                                    c_c,
+                                    c_d,
+                                    d_d,
+                                    d_c,
+                                    cooperator_frequency,
+                                    seed,
+                                };
+                                points.push((point, self.point_config(&point)?));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(points)
+    }
+
+    fn point_config(&self, point: &SweepPoint) -> Result<ExperimentConfig, CrawlError> {
+        let mut config = self.base.clone();
+        config.name = format!("{}/{}", self.base.name, point.label());
+
+        config.payoff.matrix.c_c = point.c_c;
+        config.payoff.matrix.c_d = point.c_d;
+        config.payoff.matrix.d_d = point.d_d;
+        config.payoff.matrix.d_c = point.d_c;
+
+        config.grid.rng_settings = match (point.cooperator_frequency, point.seed) {
+            (Some(frequency), seed) => {
+                // every point initialises its lattice the way the base does
+                let mut rng_settings = RngSettings::new(seed, frequency)?;
+                if let Some(base_rng) = &self.base.grid.rng_settings {
+                    rng_settings.initialisation = base_rng.initialisation;
+                }
+                Some(rng_settings)
+            }
+            (None, Some(_)) => {
+                return Err(CrawlError::Config(
+                    "sweeping seeds requires a cooperator frequency".to_string(),
+                ));
+            }
+            (None, None) => None,
+        };
+
+        Ok(config)
+    }
+
+    pub fn run(&self) -> Result<Vec<SweepRow>, CrawlError> {
+        self.run_with(&Batch::default())
+    }
+
+    pub fn run_with(&self, batch: &Batch) -> Result<Vec<SweepRow>, CrawlError> {
+        let (points, configs): (Vec<_>, Vec<_>) = self.points()?.into_iter().unzip();
+
+        let rows = batch
+            .map(&configs, |index, config| {
+                self.run_point(points[index], config)
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.write_summary(&rows)?;
+
+        Ok(rows)
+    }
+
+    pub(crate) fn run_point(
+        &self,
+        point: SweepPoint,
+        config: &ExperimentConfig,
+    ) -> Result<SweepRow, CrawlError> {
+        let mut trajectory = config.build_in(&self.output_root)?;
+        trajectory.run()?;
+
+        let directory = trajectory.directory();
+        let statistics = stats::read_statistics(&directory)?;
+
+        // the initial lattice carries no fitness so it is never averaged
+        let recorded = &statistics[1.min(statistics.len())..];
+        let window = self
+            .averaging_window
+            .unwrap_or(recorded.len())
+            .clamp(1, recorded.len().max(1));
+        let averaged = &recorded[recorded.len().saturating_sub(window)..];
+
+        let count = averaged.len().max(1) as f64;
+        let mean_cooperator_fraction =
+            averaged.iter().map(|s| s.cooperator_fraction).sum::<f64>() / count;
+        let mean_fitness = averaged.iter().map(|s| s.mean_fitness as f64).sum::<f64>() / count;
+
+        Ok(SweepRow {
+            point,
+            directory,
+            generations: trajectory.statistics().generation,
+            final_statistics: trajectory.statistics(),
+            mean_cooperator_fraction,
+            mean_fitness,
+        })
+    }
+
+    pub(crate) fn write_summary(&self, rows: &[SweepRow]) -> Result<(), CrawlError> {
+        let mut lines = vec![
+            "c_c,c_d,d_d,d_c,cooperator_frequency,seed,generations,final_cooperator_fraction,mean_cooperator_fraction,final_mean_fitness,mean_fitness,directory".to_string(),
+        ];
+        for row in rows {
+            let point = &row.point;
+            lines.push(format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{}",
+                point.c_c,
+                point.c_d,
+                point.d_d,
+                point.d_c,
+                point
+                    .cooperator_frequency
+                    .map_or(String::new(), |frequency| frequency.to_string()),
+                point.seed.map_or(String::new(), |seed| seed.to_string()),
+                row.generations,
+                row.final_statistics.cooperator_fraction,
+                row.mean_cooperator_fraction,
+                row.final_statistics.mean_fitness,
+                row.mean_fitness,
+                row.directory.display()
+            ));
+        }
+        lines.push(String::new());
+
+        let directory = self.directory();
+        std::fs::create_dir_all(&directory).map_err(CrawlError::io(&directory))?;
+
+        let summary_path = directory.join(SUMMARY_FILE);
+        std::fs::write(&summary_path, lines.join("\n")).map_err(CrawlError::io(summary_path))
+    }
+}
+
+impl SweepPoint {
+    // directory name of the point's trajectory
+    pub fn label(&self) -> String {
+        let mut label = format!(
+            "c_c={}_c_d={}_d_d={}_d_c={}",
+            self.c_c, self.c_d, self.d_d, self.d_c
+        );
+        if let Some(frequency) = self.cooperator_frequency {
+            label.push_str(&format!("_freq={frequency}"));
+        }
+        if let Some(seed) = self.seed {
+            label.push_str(&format!("_seed={seed}"));
+        }
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,190 @@
+use crate::{config::ExperimentConfig, grid::Initialisation, trajectory::TrajectoryBuilder};
+
+use super::{ParameterValues, SUMMARY_FILE, Seeds, Sweep};
+
+const TOML_SWEEP: &str = r#"
+d_c = { start = 1.6, end = 2.0, step = 0.2 }
+seeds = { count = 2 }
+averaging_window = 3
+
+[base]
+name = "test_sweep_toml"
+max_iterations = 5
+neighbourhood = ["Up", "Right", "Down", "Left"]
+
+[base.grid]
+dimension = [10, 10]
+wrapped = true
+rng_settings = { seed = 1, cooperator_frequency = 0.9 }
+
+[base.payoff]
+matrix = { c_c = 1.0, c_d = 0.0, d_d = 0.0, d_c = 1.85 }
+spatial = "None"
+"#;
+
+fn base_config(name: &str) -> ExperimentConfig {
+    TrajectoryBuilder::new(name)
+        .max_iterations(4)
+        .dimension((10, 10))
+        .seed(3)
+        .cooperator_frequency(0.9)
+        .resolve_config()
+        .unwrap()
+}
+
+fn cleanup(sweep: &Sweep) {
+    let _ = std::fs::remove_dir_all(sweep.directory());
+}
+
+#[test]
+fn test_range_values_include_end() {
+    let values = ParameterValues::Range {
+        start: 1.0,
+        end: 2.0,
+        step: 0.1,
+    }
+    .values()
+    .unwrap();
+
+    assert_eq!(values.len(), 11);
+    assert!((values[10] - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_invalid_range_is_rejected() {
+    let values = ParameterValues::Range {
+        start: 1.0,
+        end: 2.0,
+        step: 0.0,
+    };
+    assert!(values.values().is_err());
+}
+
+#[test]
+fn test_seed_count() {
+    let seeds = Seeds::Count { count: 3, start: 5 };
+    assert_eq!(seeds.values(), vec![5, 6, 7]);
+}
+
+#[test]
+fn test_points_are_cartesian_product() {
+    let mut sweep = Sweep::new(base_config("test_sweep_points"));
+    sweep.d_c = Some(ParameterValues::List(vec![1.6, 1.8, 2.0]));
+    sweep.cooperator_frequency = Some(ParameterValues::List(vec![0.8, 0.9]));
+    sweep.seeds = Some(Seeds::List(vec![1, 2]));
+
+    let points = sweep.points().unwrap();
+    assert_eq!(points.len(), 12);
+
+    for (point, config) in &points {
+        assert_eq!(config.payoff.matrix.d_c, point.d_c);
+        assert_eq!(config.payoff.matrix.c_c, 1.0);
+        let rng_settings = config.grid.rng_settings.as_ref().unwrap();
+        assert_eq!(Some(rng_settings.seed), point.seed);
+        assert_eq!(
+            Some(rng_settings.cooperator_frequency),
+            point.cooperator_frequency
+        );
+        assert!(config.name.starts_with("test_sweep_points/"));
+    }
+
+    // every combination runs under its own name so ids never collide
+    let mut names: Vec<_> = points.iter().map(|(_, config)| &config.name).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), 12);
+}
+
+#[test]
+fn test_points_keep_base_initialisation() {
+    let mut config = base_config("test_sweep_initialisation");
+    config.grid.rng_settings.as_mut().unwrap().initialisation = Initialisation::ExactCount;
+
+    let mut sweep = Sweep::new(config);
+    sweep.cooperator_frequency = Some(ParameterValues::List(vec![0.5, 0.8]));
+    sweep.seeds = Some(Seeds::List(vec![1, 2]));
+
+    let points = sweep.points().unwrap();
+    assert_eq!(points.len(), 4);
+    for (_, config) in &points {
+        assert_eq!(
+            config.grid.rng_settings.as_ref().unwrap().initialisation,
+            Initialisation::ExactCount
+        );
+    }
+}
+
+#[test]
+fn test_points_record_drawn_seeds() {
+    let mut config = base_config("test_sweep_drawn_seeds");
+    config.grid.rng_settings = None;
+
+    let mut sweep = Sweep::new(config);
+    sweep.cooperator_frequency = Some(ParameterValues::List(vec![0.5, 0.8]));
+
+    let points = sweep.points().unwrap();
+    assert_eq!(points.len(), 2);
+    for (point, config) in &points {
+        let seed = point.seed.unwrap();
+        assert_eq!(config.grid.rng_settings.as_ref().unwrap().seed, seed);
+        assert!(config.name.ends_with(&format!("_seed={seed}")));
+    }
+}
+
+#[test]
+fn test_seeds_without_frequency_are_rejected() {
+    let mut config = base_config("test_sweep_no_frequency");
+    config.grid.rng_settings = None;
+
+    let mut sweep = Sweep::new(config);
+    sweep.seeds = Some(Seeds::List(vec![1]));
+    assert!(sweep.points().is_err());
+}
+
+#[test]
+fn test_run_writes_summary() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = base_config("test_sweep_run");
+    config.grid.rng_settings = None;
+
+    let mut sweep = Sweep::new(config);
+    sweep.d_c = Some(ParameterValues::List(vec![1.2, 2.5]));
+    sweep.averaging_window = Some(2);
+
+    let rows = sweep.run()?;
+    assert_eq!(rows.len(), 2);
+
+    for row in &rows {
+        assert_eq!(row.generations, 4);
+        assert!(row.directory.starts_with(sweep.directory()));
+        assert!((0.0..=1.0).contains(&row.mean_cooperator_fraction));
+    }
+
+    // a large temptation favours defection
+    assert!(
+        rows[1].final_statistics.cooperator_fraction < rows[0].final_statistics.cooperator_fraction
+    );
+
+    let summary = std::fs::read_to_string(sweep.directory().join(SUMMARY_FILE))?;
+    let lines: Vec<_> = summary.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("c_c,c_d,d_d,d_c"));
+    assert!(lines[2].starts_with("1,0,0,2.5,,,4,"));
+
+    cleanup(&sweep);
+    Ok(())
+}
+
+#[test]
+fn test_from_toml() -> Result<(), Box<dyn std::error::Error>> {
+    let sweep: Sweep = toml::from_str(TOML_SWEEP)?;
+
+    assert_eq!(sweep.base.name, "test_sweep_toml");
+    assert_eq!(sweep.averaging_window, Some(3));
+
+    let points = sweep.points()?;
+    assert_eq!(points.len(), 6);
+    assert_eq!(points[0].0.seed, Some(0));
+    assert!((points[5].0.d_c - 2.0).abs() < 1e-6);
+
+    Ok(())
+}
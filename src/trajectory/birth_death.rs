@@ -0,0 +1,67 @@
+// fenwick tree over non-negative weights, so that a birth-death event can
+// pick its parent from the whole lattice and update the weights it changed
+// in logarithmic time
+pub(crate) struct WeightTree {
+    weights: Vec<f64>,
+    tree: Vec<f64>,
+}
+
+impl WeightTree {
+    pub(crate) fn new(weights: Vec<f64>) -> Self {
+        let mut tree = weights.clone();
+        for index in 0..tree.len() {
+            let parent = index | (index + 1);
+            if parent < tree.len() {
+                tree[parent] += tree[index];
+            }
+        }
+
+        Self { weights, tree }
+    }
+
+    pub(crate) fn total(&self) -> f64 {
+        let mut total = 0.0;
+        let mut end = self.tree.len();
+        while end > 0 {
+            total += self.tree[end - 1];
+            end &= end - 1;
+        }
+        total
+    }
+
+    pub(crate) fn set(&mut self, index: usize, weight: f64) {
+        let change = weight - self.weights[index];
+        self.weights[index] = weight;
+
+        let mut index = index;
+        while index < self.tree.len() {
+            self.tree[index] += change;
+            index |= index + 1;
+        }
+    }
+
+    // the index whose cumulative weight range contains `target`, for a
+    // target drawn uniformly from `[0, total)`
+    pub(crate) fn sample(&self, target: f64) -> usize {
+        let mut target = target;
+        let mut index = 0;
+        let mut step = self.tree.len().next_power_of_two();
+
+        while step > 0 {
+            let next = index + step;
+            if next <= self.tree.len() && self.tree[next - 1] <= target {
+                target -= self.tree[next - 1];
+                index = next;
+            }
+            step /= 2;
+        }
+
+        // rounding can carry a target past the last weight, and zero weights
+        // are never picked
+        let mut index = index.min(self.weights.len() - 1);
+        while self.weights[index] <= 0.0 && index > 0 {
+            index -= 1;
+        }
+        index
+    }
+}
@@ -0,0 +1,342 @@
+use std::path::{Path, PathBuf};
+
+use super::{DEFAULT_OUTPUT_ROOT, Schedule, Trajectory};
+use crate::{
+    CrawlError,
+    cell::BuiltinRule,
+    config::{ExperimentConfig, GridConfig},
+    grid::{Geometry, Grid, Initialisation, RngSettings},
+    neighbourhood::Neighbourhood,
+    payoff::{Payoff, PayoffMatrix, SpatialPayoff},
+    render::GifOptions,
+    snapshot,
+};
+
+// assembles a trajectory from defaults, a nowak-may run from a lone defector on
+// a wrapped 100x100 moore lattice with b = 1.9, everything is validated
+// before anything is written to disk
+#[derive(Debug)]
+pub struct TrajectoryBuilder {
+    config: ExperimentConfig,
+    grid: Option<Grid>,
+    seed: Option<u64>,
+    cooperator_frequency: Option<f64>,
+    initialisation: Initialisation,
+    threads: usize,
+    output_root: PathBuf,
+}
+
+impl TrajectoryBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            config: ExperimentConfig {
+                name: name.into(),
+                max_iterations: 100,
+                grid: GridConfig {
+                    dimension: (100, 100),
+                    layers: 1,
+                    wrapped: true,
+                    geometry: Geometry::Square,
+                    rng_settings: None,
+                },
+                initial_snapshot: None,
+                neighbourhood: Neighbourhood::moore().shape().clone(),
+                imitation_neighbourhood: None,
+                include_self: false,
+                imitation_include_self: None,
+                payoff: Payoff::new(PayoffMatrix::new(1.0, 0.0, 0.0, 1.9)),
+                update_rule: BuiltinRule::ImitateBest,
+                schedule: Schedule::default(),
+                stop_on_cycle: false,
+                detect_cycles: false,
+                snapshot_interval: None,
+                gif: None,
+            },
+            grid: None,
+            seed: None,
+            cooperator_frequency: None,
+            initialisation: Initialisation::default(),
+            threads: 1,
+            output_root: PathBuf::from(DEFAULT_OUTPUT_ROOT),
+        }
+    }
+
+    pub fn from_config(config: ExperimentConfig) -> Self {
+        let rng_settings = config.grid.rng_settings.clone();
+
+        let mut builder = Self::new(config.name.clone());
+        builder.config = config;
+        if let Some(rng_settings) = rng_settings {
+            builder.seed = Some(rng_settings.seed);
+            builder.cooperator_frequency = Some(rng_settings.cooperator_frequency);
+            builder.initialisation = rng_settings.initialisation;
+        }
+        builder
+    }
+
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.config.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn dimension(mut self, dimension: (i32, i32)) -> Self {
+        self.config.grid.dimension = dimension;
+        self
+    }
+
+    pub fn wrapped(mut self, wrapped: bool) -> Self {
+        self.config.grid.wrapped = wrapped;
+        self
+    }
+
+    // a random initial lattice, without it a lone defector starts in the
+    // centre
+    pub fn cooperator_frequency(mut self, cooperator_frequency: f64) -> Self {
+        self.cooperator_frequency = Some(cooperator_frequency);
+        self
+    }
+
+    // seed of the initial lattice and the dynamics, random if unset
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn initialisation(mut self, initialisation: Initialisation) -> Self {
+        self.initialisation = initialisation;
+        self
+    }
+
+    pub fn rng_settings(mut self, rng_settings: RngSettings) -> Self {
+        self.seed = Some(rng_settings.seed);
+        self.cooperator_frequency = Some(rng_settings.cooperator_frequency);
+        self.initialisation = rng_settings.initialisation;
+        self
+    }
+
+    // layers of a cubic lattice, each of `dimension`
+    pub fn layers(mut self, layers: i32) -> Self {
+        self.config.grid.layers = layers;
+        self
+    }
+
+    // switching geometry also swaps a neighbourhood of the old geometry for
+    // the nearest neighbours of the new one
+    pub fn geometry(mut self, geometry: Geometry) -> Self {
+        self.config.grid.geometry = geometry;
+        self.fit_neighbourhood(geometry);
+        self
+    }
+
+    // starts from an existing lattice, e.g. one loaded from a snapshot, in
+    // place of the dimension, wrapping, geometry and rng settings. the grid's
+    // rng settings still seed the dynamics, and a lattice they do not generate
+    // is recorded as the run's initial snapshot
+    pub fn grid(mut self, grid: Grid) -> Self {
+        self.fit_neighbourhood(grid.geometry);
+        self.grid = Some(grid);
+        self
+    }
+
+    fn fit_neighbourhood(&mut self, geometry: Geometry) {
+        if self.config.neighbourhood.check_geometry(geometry).is_err() {
+            self.config.neighbourhood = Neighbourhood::nearest(geometry).shape().clone();
+        }
+    }
+
+    pub fn neighbourhood(mut self, neighbourhood: Neighbourhood) -> Self {
+        self.config.neighbourhood = neighbourhood.shape().clone();
+        self.config.include_self = neighbourhood.includes_self();
+        self
+    }
+
+    // neighbours a cell learns from, keeping whether it includes the cell
+    pub fn imitation_neighbourhood(mut self, neighbourhood: Neighbourhood) -> Self {
+        self.config.imitation_neighbourhood = Some(neighbourhood.shape().clone());
+        self.config.imitation_include_self = Some(neighbourhood.includes_self());
+        self
+    }
+
+    // self-interaction and self-imitation alike
+    pub fn include_self(mut self, include_self: bool) -> Self {
+        self.config.include_self = include_self;
+        self.config.imitation_include_self = None;
+        self
+    }
+
+    pub fn payoff(mut self, payoff: Payoff) -> Self {
+        self.config.payoff = payoff;
+        self
+    }
+
+    pub fn payoff_matrix(mut self, matrix: PayoffMatrix) -> Self {
+        self.config.payoff.matrix = matrix;
+        self
+    }
+
+    pub fn spatial_payoff(mut self, spatial: SpatialPayoff) -> Self {
+        self.config.payoff.spatial = spatial;
+        self
+    }
+
+    pub fn update_rule(mut self, update_rule: BuiltinRule) -> Self {
+        self.config.update_rule = update_rule;
+        self
+    }
+
+    pub fn schedule(mut self, schedule: Schedule) -> Self {
+        self.config.schedule = schedule;
+        self
+    }
+
+    pub fn stop_on_cycle(mut self, stop_on_cycle: bool) -> Self {
+        self.config.stop_on_cycle = stop_on_cycle;
+        self
+    }
+
+    pub fn detect_cycles(mut self, detect_cycles: bool) -> Self {
+        self.config.detect_cycles = detect_cycles;
+        self
+    }
+
+    pub fn snapshot_interval(mut self, snapshot_interval: usize) -> Self {
+        self.config.snapshot_interval = Some(snapshot_interval);
+        self
+    }
+
+    pub fn gif(mut self, gif: GifOptions) -> Self {
+        self.config.gif = Some(gif);
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn output_root(mut self, output_root: impl AsRef<Path>) -> Self {
+        self.output_root = output_root.as_ref().to_path_buf();
+        self
+    }
+
+    // the config the built trajectory records in its metadata, tests also
+    // start from it rather than spelling out every field
+    pub(crate) fn resolve_config(&self) -> Result<ExperimentConfig, CrawlError> {
+        let mut config = self.config.clone();
+
+        config.grid = match &self.grid {
+            Some(grid) => {
+                // known once the trajectory has written it
+                config.initial_snapshot = None;
+                GridConfig {
+                    dimension: grid.dimension,
+                    layers: grid.layers,
+                    wrapped: grid.wrapped,
+                    geometry: grid.geometry,
+                    rng_settings: grid.rng_settings.clone(),
+                }
+            }
+            None => GridConfig {
+                rng_settings: self.rng_settings_or_none()?,
+                ..config.grid
+            },
+        };
+        // only a difference is recorded, as in `Trajectory::config`
+        if config.imitation_neighbourhood.is_none()
+            || config.imitation_include_self == Some(config.include_self)
+        {
+            config.imitation_include_self = None;
+        }
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn build(self) -> Result<Trajectory, CrawlError> {
+        let config = self.resolve_config()?;
+
+        let grid = match self.grid {
+            Some(grid) => grid,
+            None if let Some(path) = &config.initial_snapshot => {
+                load_initial_snapshot(path, &config.grid)?
+            }
+            None if config.grid.geometry == Geometry::Cubic => Grid::cubic(
+                config.grid.dimension,
+                config.grid.layers,
+                config.grid.wrapped,
+                config.grid.rng_settings.clone(),
+            )?,
+            None => Grid::new(
+                config.grid.dimension,
+                config.grid.wrapped,
+                config.grid.rng_settings.clone(),
+            )?
+            .with_geometry(config.grid.geometry)?,
+        };
+
+        let mut trajectory = Trajectory::new_in(
+            self.output_root,
+            config.name,
+            config.max_iterations,
+            grid,
+            Neighbourhood::from_shape(config.neighbourhood).with_self(config.include_self),
+            config.payoff,
+            config.update_rule,
+            config.schedule,
+        )?;
+        trajectory.imitation_neighbourhood = config.imitation_neighbourhood.map(|shape| {
+            Neighbourhood::from_shape(shape)
+                .with_self(config.imitation_include_self.unwrap_or(config.include_self))
+        });
+        trajectory.stop_on_cycle = config.stop_on_cycle;
+        trajectory.detect_cycles = config.detect_cycles;
+        trajectory.snapshot_interval = config.snapshot_interval;
+        trajectory.gif = config.gif;
+        trajectory.threads = self.threads;
+        trajectory.write_metadata()?;
+
+        Ok(trajectory)
+    }
+
+    fn rng_settings_or_none(&self) -> Result<Option<RngSettings>, CrawlError> {
+        match (self.cooperator_frequency, self.seed) {
+            (Some(cooperator_frequency), seed) => {
+                let mut rng_settings = RngSettings::new(seed, cooperator_frequency)?;
+                rng_settings.initialisation = self.initialisation;
+                Ok(Some(rng_settings))
+            }
+            (None, Some(_)) => Err(CrawlError::Config(
+                "a seed requires a cooperator frequency".to_string(),
+            )),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+// the snapshot must hold a lattice of the configured shape
+fn load_initial_snapshot(path: &Path, grid: &GridConfig) -> Result<Grid, CrawlError> {
+    let snapshot = snapshot::read_snapshot_file(path)?;
+    if (snapshot.dimension, snapshot.layers) != (grid.dimension, grid.layers) {
+        return Err(CrawlError::Config(format!(
+            "initial snapshot {} holds a {}x{}x{} lattice, expected {}x{}x{}",
+            path.display(),
+            snapshot.layers,
+            snapshot.dimension.0,
+            snapshot.dimension.1,
+            grid.layers,
+            grid.dimension.0,
+            grid.dimension.1
+        )));
+    }
+
+    snapshot
+        .into_grid(grid.wrapped, grid.rng_settings.clone())?
+        .with_geometry(grid.geometry)
+}
+
+impl Trajectory {
+    #[inline]
+    pub fn builder(name: impl Into<String>) -> TrajectoryBuilder {
+        TrajectoryBuilder::new(name)
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Cycle {
+    // generation at which the lattice first enters the cycle
+    pub transient: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    #[inline]
+    pub fn is_fixed_point(&self) -> bool {
+        self.period == 1
+    }
+}
+
+// only hashes are kept, lattices would cost a full copy per generation
+#[derive(Debug, Default)]
+pub(crate) struct CycleDetector {
+    seen: HashMap<u64, Vec<usize>>,
+    cycle: Option<Cycle>,
+}
+
+impl CycleDetector {
+    // earlier generations whose lattice hashed to `hash`, oldest first. equal
+    // hashes are only candidates, the caller compares the lattices
+    pub(crate) fn candidates(&self, hash: u64) -> &[usize] {
+        self.seen.get(&hash).map_or(&[], Vec::as_slice)
+    }
+
+    // records the hash of `generation` and `recurrence`, the earliest of its
+    // candidates holding the same lattice if any. generations must be
+    // observed in order starting from zero
+    pub(crate) fn observe(&mut self, generation: usize, hash: u64, recurrence: Option<usize>) {
+        if self.cycle.is_some() {
+            return;
+        }
+
+        if let Some(first) = recurrence {
+            self.cycle = Some(Cycle {
+                transient: first,
+                period: generation - first,
+            });
+
+            // nothing more can be learnt once the cycle is known
+            self.seen = HashMap::new();
+            return;
+        }
+
+        self.seen.entry(hash).or_default().push(generation);
+    }
+
+    #[inline]
+    pub(crate) fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+}
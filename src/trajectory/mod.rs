@@ -1,28 +1,86 @@
-use serde::{Deserialize, Serialize};
+mod birth_death;
+mod builder;
+mod cycle;
+mod parallel;
+mod replay;
+mod schedule;
+
+pub use builder::TrajectoryBuilder;
+pub use cycle::Cycle;
+pub use replay::{ReplayReport, read_history};
+pub use schedule::Schedule;
+
+pub const HISTORY_FILE: &str = "history.txt";
+pub const DEFAULT_OUTPUT_ROOT: &str = "trajectories";
+
+// gives up on claiming a run directory after this many taken ids
+const MAX_ID_ATTEMPTS: usize = 10_000;
+
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
 use std::{
-    fs::File,
+    fs::OpenOptions,
     io::Write,
-    path::Path,
+    path::{Component, Path, PathBuf},
     time::{SystemTime, UNIX_EPOCH},
 };
 
+use birth_death::WeightTree;
+use cycle::CycleDetector;
+use parallel::CellRng;
+
 use crate::{
-    grid::{Grid, RngSettings},
-    neighbourhood::{Direction, Neighbourhood},
+    CrawlError,
+    cell::{BuiltinRule, Cell, UpdateRule},
+    config::{ExperimentConfig, GridConfig},
+    grid::{self, Grid},
+    neighbourhood::Neighbourhood,
     payoff::Payoff,
+    render::{GIF_FILE, GifOptions, GifRecorder},
+    snapshot::{self, Snapshot},
+    stats::{self, Statistics},
 };
 
 #[derive(Debug)]
 pub struct Trajectory {
     id: String,
+    output_root: PathBuf,
     pub name: String,
     pub max_iterations: usize,
+    pub stop_on_cycle: bool,
+    // confirming a cycle replays the run from generation zero, implied by
+    // `stop_on_cycle`
+    pub detect_cycles: bool,
+    pub snapshot_interval: Option<usize>,
+    pub gif: Option<GifOptions>,
+    // threads sharing a synchronous generation, results are identical for any
+    // count while asynchronous schedules always run on one thread
+    pub threads: usize,
+    // neighbours a cell learns from, `None` imitates the neighbours it plays
+    // against
+    pub imitation_neighbourhood: Option<Neighbourhood>,
     curr_iteration: usize,
     grid: Grid,
     neighbourhood: Neighbourhood,
     payoff: Payoff,
+    update_rule: BuiltinRule,
+    schedule: Schedule,
+    rng: ChaCha8Rng,
 
     history: Vec<u64>,
+    cycle_detector: CycleDetector,
+    // the generation zero lattice that cycle candidates are replayed from
+    initial_lattice: Vec<u8>,
+    // where that lattice was saved if the grid's rng settings do not
+    // generate it
+    initial_snapshot: Option<PathBuf>,
+    // the imitation neighbourhood of the first generation and whether the
+    // trajectory's own rule drove it. a replay is only faithful, and a cycle
+    // only confirmed, while every generation has matched them
+    initial_dynamics: Option<(Option<Neighbourhood>, bool)>,
+    replayable: bool,
+    gif_recorder: Option<GifRecorder>,
 }
 
 impl Trajectory {
@@ -32,20 +90,93 @@ impl Trajectory {
         grid: Grid,
         neighbourhood: Neighbourhood,
         payoff: Payoff,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+        update_rule: BuiltinRule,
+        schedule: Schedule,
+    ) -> Result<Self, CrawlError> {
+        Self::new_in(
+            DEFAULT_OUTPUT_ROOT,
+            name,
+            max_iterations,
+            grid,
+            neighbourhood,
+            payoff,
+            update_rule,
+            schedule,
+        )
+    }
+
+    // stores the trajectory under `output_root/name/id` instead of the default
+    // `trajectories/` root
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_in(
+        output_root: impl AsRef<Path>,
+        name: String,
+        max_iterations: usize,
+        grid: Grid,
+        neighbourhood: Neighbourhood,
+        payoff: Payoff,
+        update_rule: BuiltinRule,
+        schedule: Schedule,
+    ) -> Result<Self, CrawlError> {
+        grid::layered_cell_count(grid.dimension, grid.layers)?;
+        grid.geometry
+            .check_dimension(grid.dimension, grid.layers, grid.wrapped)?;
+        neighbourhood.shape().check_geometry(grid.geometry)?;
+        neighbourhood
+            .shape()
+            .check_dimension(grid.dimension, grid.wrapped)?;
+        payoff.spatial.check_dimension(grid.dimension)?;
+        update_rule.check()?;
+
+        check_name(&name)?;
+
+        let output_root = output_root.as_ref().to_path_buf();
+        let id = claim_id(&output_root.join(&name))?;
+
+        let initial_snapshot = if grid.is_generated() {
+            None
+        } else {
+            let path = output_root
+                .join(&name)
+                .join(&id)
+                .join(snapshot::INITIAL_SNAPSHOT_FILE);
+            std::fs::write(&path, Snapshot::from_grid(0, &grid).to_bytes())
+                .map_err(CrawlError::io(&path))?;
+            Some(std::path::absolute(&path).map_err(CrawlError::io(&path))?)
+        };
+
+        let initial_hash = grid.get_lattice_hash();
+        let rng = dynamics_rng(&grid);
+
+        let mut cycle_detector = CycleDetector::default();
+        cycle_detector.observe(0, initial_hash, None);
+        let initial_lattice = grid.encode_lattice();
+
         let trajectory = Self {
-            id: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-                .to_string(),
+            id,
+            output_root,
             name,
             max_iterations,
+            stop_on_cycle: false,
+            detect_cycles: false,
+            snapshot_interval: None,
+            gif: None,
+            threads: 1,
+            imitation_neighbourhood: None,
             curr_iteration: 0,
             grid,
             neighbourhood,
             payoff,
-            history: vec![],
+            update_rule,
+            schedule,
+            rng,
+            history: vec![initial_hash],
+            cycle_detector,
+            initial_lattice,
+            initial_snapshot,
+            initial_dynamics: None,
+            replayable: true,
+            gif_recorder: None,
         };
 
         trajectory.initialize_trajectory()?;
@@ -53,50 +184,645 @@ impl Trajectory {
         Ok(trajectory)
     }
 
-    fn initialize_trajectory(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let base_path = Path::new("trajectories").join(&self.name).join(&self.id);
-        std::fs::create_dir_all(&base_path.clone())?;
+    pub fn step(&mut self) -> Result<(), CrawlError> {
+        let update_rule = self.update_rule;
+        self.step_by(&update_rule, true)
+    }
 
-        let metadata_json = self.serialize_metadata()?;
+    // advances one generation using `rule` in place of the trajectory's own
+    // update rule. a cycle is confirmed by replaying with `rule`, so cycle
+    // detection assumes every call passes the same rule and stops confirming
+    // cycles once `step` and `step_with` are mixed
+    pub fn step_with(&mut self, rule: &dyn UpdateRule) -> Result<(), CrawlError> {
+        self.step_by(rule, false)
+    }
 
-        let metadata_path = base_path.join("metadata.json");
-        let mut metadata_file = File::create(metadata_path)?;
-        metadata_file.write_all(metadata_json.as_bytes())?;
+    fn step_by(&mut self, rule: &dyn UpdateRule, own_rule: bool) -> Result<(), CrawlError> {
+        if self.curr_iteration == 0 {
+            self.record_generation()?;
+        }
 
-        Ok(())
+        let dynamics = (self.imitation_neighbourhood.clone(), own_rule);
+        match &self.initial_dynamics {
+            None => self.initial_dynamics = Some(dynamics),
+            Some(initial) if *initial != dynamics => self.replayable = false,
+            Some(_) => {}
+        }
+
+        self.advance(rule);
+
+        let hash = self.grid.get_lattice_hash();
+        self.history.push(hash);
+
+        let candidates = self.cycle_detector.candidates(hash);
+        let recurrence = if self.detects_cycles() && self.replayable && !candidates.is_empty() {
+            self.first_recurrence(rule, candidates)?
+        } else {
+            None
+        };
+        self.cycle_detector
+            .observe(self.curr_iteration, hash, recurrence);
+
+        self.record_generation()?;
+        self.append_generation()
     }
 
-    fn serialize_metadata(&self) -> Result<String, serde_json::Error> {
-        #[derive(Serialize)]
-        struct TrajectoryMetadata<'a> {
-            id: &'a str,
-            name: &'a str,
-            max_iterations: usize,
-            neighbourhood: Vec<Direction>,
-            payoff: Payoff,
-            grid: GridMetadata,
+    // one generation of the dynamics without any output
+    fn advance(&mut self, rule: &dyn UpdateRule) {
+        self.accumulate_payoffs();
+
+        match self.schedule {
+            Schedule::Synchronous if !rule.is_birth_death() => self.update_strategies(rule),
+            schedule => {
+                // a cell may be drawn several times or not at all, so its
+                // transition is taken over the whole generation
+                let start: Vec<bool> = self.grid.lattice.iter().map(Cell::is_cooperator).collect();
+                if rule.is_birth_death() {
+                    self.birth_death();
+                } else {
+                    self.update_asynchronously(schedule, rule);
+                }
+                for (cell, was_cooperator) in self.grid.lattice.iter_mut().zip(start) {
+                    let is_cooperator = cell.is_cooperator();
+                    cell.set_transition(was_cooperator, is_cooperator);
+                }
+            }
         }
 
-        #[derive(Serialize)]
-        struct GridMetadata {
-            dimension: (i32, i32),
-            wrapped: bool,
-            rng_settings: Option<RngSettings>,
+        self.curr_iteration += 1;
+    }
+
+    // the earliest of `candidates` whose lattice equals the current one,
+    // found by replaying a copy of the run without output
+    fn first_recurrence(
+        &self,
+        rule: &dyn UpdateRule,
+        candidates: &[usize],
+    ) -> Result<Option<usize>, CrawlError> {
+        let lattice = self.grid.encode_lattice();
+
+        let grid = Grid::from_encoded_layers(
+            self.grid.dimension,
+            self.grid.layers,
+            self.grid.wrapped,
+            self.grid.rng_settings.clone(),
+            &self.initial_lattice,
+        )?
+        .with_geometry(self.grid.geometry)?;
+        let mut replay = Self {
+            id: self.id.clone(),
+            output_root: self.output_root.clone(),
+            name: self.name.clone(),
+            max_iterations: self.max_iterations,
+            stop_on_cycle: false,
+            detect_cycles: false,
+            snapshot_interval: None,
+            gif: None,
+            threads: self.threads,
+            imitation_neighbourhood: self.imitation_neighbourhood.clone(),
+            curr_iteration: 0,
+            rng: dynamics_rng(&grid),
+            grid,
+            neighbourhood: self.neighbourhood.clone(),
+            payoff: self.payoff.clone(),
+            update_rule: self.update_rule,
+            schedule: self.schedule,
+            history: Vec::new(),
+            cycle_detector: CycleDetector::default(),
+            initial_lattice: Vec::new(),
+            initial_snapshot: None,
+            initial_dynamics: None,
+            replayable: false,
+            gif_recorder: None,
+        };
+
+        for &candidate in candidates {
+            while replay.curr_iteration < candidate {
+                replay.advance(rule);
+            }
+            if replay.grid.encode_lattice() == lattice {
+                return Ok(Some(candidate));
+            }
         }
+        Ok(None)
+    }
 
-        let metadata = TrajectoryMetadata {
-            name: &self.name,
-            id: &self.id,
+    pub fn run(&mut self) -> Result<(), CrawlError> {
+        while self.curr_iteration < self.max_iterations {
+            self.step()?;
+
+            if self.stop_on_cycle && self.cycle().is_some() {
+                break;
+            }
+        }
+
+        self.record_final_generation()?;
+        if self.detects_cycles() {
+            self.write_cycle_summary()?;
+        }
+        Ok(())
+    }
+
+    // the configuration that reproduces this trajectory from generation zero
+    pub fn config(&self) -> ExperimentConfig {
+        ExperimentConfig {
+            name: self.name.clone(),
             max_iterations: self.max_iterations,
-            neighbourhood: self.neighbourhood.get_directions().into(),
-            payoff: self.payoff,
-            grid: GridMetadata {
+            grid: GridConfig {
                 dimension: self.grid.dimension,
+                layers: self.grid.layers,
                 wrapped: self.grid.wrapped,
+                geometry: self.grid.geometry,
                 rng_settings: self.grid.rng_settings.clone(),
             },
+            initial_snapshot: self.initial_snapshot.clone(),
+            neighbourhood: self.neighbourhood.shape().clone(),
+            imitation_neighbourhood: self
+                .imitation_neighbourhood
+                .as_ref()
+                .map(|neighbourhood| neighbourhood.shape().clone()),
+            include_self: self.neighbourhood.includes_self(),
+            imitation_include_self: self
+                .imitation_neighbourhood
+                .as_ref()
+                .map(Neighbourhood::includes_self)
+                .filter(|&include_self| include_self != self.neighbourhood.includes_self()),
+            payoff: self.payoff.clone(),
+            update_rule: self.update_rule,
+            schedule: self.schedule,
+            stop_on_cycle: self.stop_on_cycle,
+            detect_cycles: self.detect_cycles,
+            snapshot_interval: self.snapshot_interval,
+            gif: self.gif,
+        }
+    }
+
+    // lattice hashes of every generation so far, starting with the initial
+    // lattice
+    #[inline]
+    pub fn history(&self) -> &[u64] {
+        &self.history
+    }
+
+    #[inline]
+    pub fn statistics(&self) -> Statistics {
+        Statistics::from_grid(self.curr_iteration, &self.grid)
+    }
+
+    // the lattice of the current generation
+    #[inline]
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    // generations advanced so far, zero before the first step
+    #[inline]
+    pub fn generation(&self) -> usize {
+        self.curr_iteration
+    }
+
+    // the first recurrence of a lattice state, under stochastic rules or
+    // schedules this is only a true cycle for absorbing states. always `None`
+    // unless cycles are detected
+    #[inline]
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle_detector.cycle()
+    }
+
+    #[inline]
+    pub fn detects_cycles(&self) -> bool {
+        self.detect_cycles || self.stop_on_cycle
+    }
+
+    // writes the snapshot and gif frame of the current generation if it falls
+    // on their configured intervals
+    fn record_generation(&mut self) -> Result<(), CrawlError> {
+        let generation = self.curr_iteration;
+
+        // output options are public fields and may have changed since the
+        // metadata was first written
+        if generation == 0 {
+            self.write_metadata()?;
+        }
+
+        if self
+            .snapshot_interval
+            .is_some_and(|interval| generation.is_multiple_of(interval))
+        {
+            snapshot::write_snapshot(&self.directory(), generation, &self.grid)?;
+        }
+
+        if generation == 0
+            && let Some(options) = self.gif
+        {
+            self.gif_recorder = Some(GifRecorder::create(
+                &self.directory().join(GIF_FILE),
+                self.grid.dimension,
+                self.grid.geometry,
+                options,
+            )?);
+        }
+
+        if let Some(recorder) = &mut self.gif_recorder
+            && generation.is_multiple_of(recorder.options().stride)
+        {
+            recorder.write_frame(&self.grid)?;
+        }
+
+        Ok(())
+    }
+
+    // always keeps the final lattice, even when it falls between intervals,
+    // and completes the gif
+    fn record_final_generation(&mut self) -> Result<(), CrawlError> {
+        let generation = self.curr_iteration;
+
+        if self
+            .snapshot_interval
+            .is_some_and(|interval| !generation.is_multiple_of(interval))
+        {
+            snapshot::write_snapshot(&self.directory(), generation, &self.grid)?;
+        }
+
+        if let Some(mut recorder) = self.gif_recorder.take() {
+            if !generation.is_multiple_of(recorder.options().stride) {
+                recorder.write_frame(&self.grid)?;
+            }
+            recorder.finish()?;
+        }
+
+        Ok(())
+    }
+
+    fn accumulate_payoffs(&mut self) {
+        let mut fitnesses = vec![0.0; self.grid.lattice.len()];
+        parallel::fill_bands(
+            &mut fitnesses,
+            self.grid.dimension.1 as usize,
+            self.threads,
+            |start, band| {
+                for (offset, fitness) in band.iter_mut().enumerate() {
+                    *fitness = self.cell_payoff(start + offset);
+                }
+            },
+        );
+
+        for (cell, fitness) in self.grid.lattice.iter_mut().zip(fitnesses) {
+            cell.set_fitness(fitness);
+        }
+    }
+
+    // spatial payoffs see the cell's position within its layer
+    fn cell_payoff(&self, index: usize) -> f32 {
+        let cell = &self.grid.lattice[index];
+        let coordinates = self.grid.get_coordinates(index);
+
+        self.opponents(index)
+            .map(|neighbour| self.payoff.get_payoff(cell, neighbour, Some(coordinates)))
+            .sum()
+    }
+
+    // strategies are collected before any cell is touched so that every cell
+    // learns from the generation it actually played against
+    fn update_strategies(&mut self, rule: &dyn UpdateRule) {
+        let generation_seed: u64 = self.rng.r#gen();
+
+        let mut strategies = vec![false; self.grid.lattice.len()];
+        parallel::fill_bands(
+            &mut strategies,
+            self.grid.dimension.1 as usize,
+            self.threads,
+            |start, band| {
+                let mut neighbours: Vec<&Cell> = Vec::new();
+                for (offset, to_cooperator) in band.iter_mut().enumerate() {
+                    let index = start + offset;
+
+                    neighbours.clear();
+                    neighbours.extend(neighbours_of(
+                        &self.grid,
+                        self.imitation_neighbourhood(),
+                        index,
+                    ));
+                    *to_cooperator = rule.next_strategy(
+                        &self.grid.lattice[index],
+                        &neighbours,
+                        &mut CellRng::new(generation_seed, index),
+                    );
+                }
+            },
+        );
+
+        for (cell, to_cooperator) in self.grid.lattice.iter_mut().zip(strategies) {
+            cell.update_strategy(to_cooperator);
+        }
+    }
+
+    // runs the elementary updates of one asynchronous generation, the
+    // transitions they leave behind are only meaningful for the strategy
+    fn update_asynchronously(&mut self, schedule: Schedule, rule: &dyn UpdateRule) {
+        match schedule {
+            Schedule::Synchronous => {
+                unreachable!("synchronous generations update every cell at once")
+            }
+            Schedule::RandomSequential => {
+                for _ in 0..self.grid.lattice.len() {
+                    let index = self.rng.gen_range(0..self.grid.lattice.len());
+                    self.update_cell(index, rule);
+                }
+            }
+            Schedule::RandomPermutation => {
+                let mut order: Vec<usize> = (0..self.grid.lattice.len()).collect();
+                order.shuffle(&mut self.rng);
+                for index in order {
+                    self.update_cell(index, rule);
+                }
+            }
+            Schedule::PoissonClock => {
+                // the superposition of the cells' clocks fires at rate n, and
+                // by memorylessness the overshoot past the generation boundary
+                // can be discarded
+                let rate = self.grid.lattice.len() as f64;
+                let mut time = 0.0;
+                loop {
+                    time += -(1.0 - self.rng.r#gen::<f64>()).ln() / rate;
+                    if time >= 1.0 {
+                        break;
+                    }
+                    let index = self.rng.gen_range(0..self.grid.lattice.len());
+                    self.update_cell(index, rule);
+                }
+            }
+        }
+    }
+
+    // one event per cell: a parent drawn from the whole lattice in proportion
+    // to its fitness replaces a random imitation neighbour with its offspring.
+    // the cells around a replaced neighbour are rescored before the next
+    // event, as in `update_cell`
+    fn birth_death(&mut self) {
+        let floor = self.fitness_floor();
+        let mut weights = WeightTree::new(
+            self.grid
+                .lattice
+                .iter()
+                .map(|cell| f64::from(cell.get_fitness() - floor))
+                .collect(),
+        );
+
+        for _ in 0..self.grid.lattice.len() {
+            let total = weights.total();
+            let parent = if total > 0.0 {
+                weights.sample(self.rng.r#gen::<f64>() * total)
+            } else {
+                self.rng.gen_range(0..self.grid.lattice.len())
+            };
+
+            let neighbours: Vec<usize> =
+                neighbour_indices(&self.grid, self.imitation_neighbourhood(), parent).collect();
+            if neighbours.is_empty() {
+                continue;
+            }
+            let child = neighbours[self.rng.gen_range(0..neighbours.len())];
+
+            let to_cooperator = self.grid.lattice[parent].is_cooperator();
+            if self.grid.lattice[child].is_cooperator() == to_cooperator {
+                continue;
+            }
+            self.grid.lattice[child].update_strategy(to_cooperator);
+
+            let affected: Vec<usize> = std::iter::once(child)
+                .chain(neighbour_indices(&self.grid, &self.neighbourhood, child))
+                .collect();
+            for affected in affected {
+                let fitness = self.cell_payoff(affected);
+                self.grid.lattice[affected].set_fitness(fitness);
+                weights.set(affected, f64::from(fitness - floor));
+            }
+        }
+    }
+
+    // no cell can score below this however the lattice changes, since every
+    // game pays at least the smallest matrix entry and spatial term. parents
+    // are weighted by their fitness above it
+    fn fitness_floor(&self) -> f32 {
+        let matrix = &self.payoff.matrix;
+        let spatial = (0..self.grid.lattice.len())
+            .map(|index| {
+                self.payoff
+                    .spatial
+                    .get_payoff(self.grid.get_coordinates(index))
+            })
+            .fold(f32::INFINITY, f32::min);
+        let game = matrix.c_c.min(matrix.c_d).min(matrix.d_d).min(matrix.d_c) + spatial;
+
+        // cells on a bounded edge play fewer games, which only raises a
+        // negative bound
+        (game * self.neighbourhood.len() as f32).min(0.0)
+    }
+
+    // asynchronous elementary update: the payoffs the cell compares against
+    // are refreshed from the current lattice before it picks a strategy
+    fn update_cell(&mut self, index: usize, rule: &dyn UpdateRule) {
+        let affected: Vec<usize> = std::iter::once(index)
+            .chain(neighbour_indices(
+                &self.grid,
+                self.imitation_neighbourhood(),
+                index,
+            ))
+            .collect();
+        for affected in affected {
+            let fitness = self.cell_payoff(affected);
+            self.grid.lattice[affected].set_fitness(fitness);
+        }
+
+        // borrowed field by field so the rng stays free
+        let imitation_neighbourhood = self
+            .imitation_neighbourhood
+            .as_ref()
+            .unwrap_or(&self.neighbourhood);
+        let neighbours: Vec<&Cell> =
+            neighbours_of(&self.grid, imitation_neighbourhood, index).collect();
+        let to_cooperator =
+            rule.next_strategy(&self.grid.lattice[index], &neighbours, &mut self.rng);
+
+        self.grid.lattice[index].update_strategy(to_cooperator);
+    }
+
+    #[inline]
+    fn imitation_neighbourhood(&self) -> &Neighbourhood {
+        self.imitation_neighbourhood
+            .as_ref()
+            .unwrap_or(&self.neighbourhood)
+    }
+
+    fn opponents(&self, index: usize) -> impl Iterator<Item = &Cell> {
+        neighbours_of(&self.grid, &self.neighbourhood, index)
+    }
+
+    pub fn directory(&self) -> PathBuf {
+        self.output_root.join(&self.name).join(&self.id)
+    }
+
+    #[inline]
+    pub fn output_root(&self) -> &Path {
+        &self.output_root
+    }
+
+    fn initialize_trajectory(&self) -> Result<(), CrawlError> {
+        self.write_metadata()?;
+
+        let statistics_path = self.directory().join(stats::STATISTICS_FILE);
+        let statistics = format!(
+            "{}\n{}\n",
+            Statistics::csv_header(),
+            self.statistics().to_csv_row()
+        );
+        std::fs::write(&statistics_path, statistics).map_err(CrawlError::io(statistics_path))?;
+
+        let history_path = self.directory().join(HISTORY_FILE);
+        std::fs::write(&history_path, format!("{:016x}\n", self.history[0]))
+            .map_err(CrawlError::io(history_path))
+    }
+
+    pub(crate) fn write_metadata(&self) -> Result<(), CrawlError> {
+        let metadata_path = self.directory().join("metadata.json");
+        std::fs::write(&metadata_path, self.serialize_metadata()?)
+            .map_err(CrawlError::io(metadata_path))
+    }
+
+    fn append_generation(&self) -> Result<(), CrawlError> {
+        append_line(
+            &self.directory().join(stats::STATISTICS_FILE),
+            &self.statistics().to_csv_row(),
+        )?;
+        append_line(
+            &self.directory().join(HISTORY_FILE),
+            &format!("{:016x}", self.history[self.curr_iteration]),
+        )
+    }
+
+    fn write_cycle_summary(&self) -> Result<(), CrawlError> {
+        #[derive(Serialize)]
+        struct CycleSummary {
+            generations: usize,
+            cycle: Option<Cycle>,
+            fixed_point: bool,
+        }
+
+        let cycle = self.cycle();
+        let summary = CycleSummary {
+            generations: self.curr_iteration,
+            cycle,
+            fixed_point: cycle.is_some_and(|cycle| cycle.is_fixed_point()),
+        };
+
+        let summary_path = self.directory().join("cycle.json");
+        std::fs::write(&summary_path, serde_json::to_string_pretty(&summary)?)
+            .map_err(CrawlError::io(summary_path))
+    }
+
+    fn serialize_metadata(&self) -> Result<String, serde_json::Error> {
+        #[derive(Serialize)]
+        struct TrajectoryMetadata<'a> {
+            id: &'a str,
+            #[serde(flatten)]
+            config: ExperimentConfig,
+        }
+
+        let metadata = TrajectoryMetadata {
+            id: &self.id,
+            config: self.config(),
         };
 
         serde_json::to_string_pretty(&metadata)
     }
 }
+
+// claims a fresh run directory under `parent`, named by the current UNIX time
+// with a numeric suffix when runs started in the same second already took it,
+// so an existing run is never written into
+// names become directories below the output root and may nest, as sweep
+// points do, but must not climb out of it
+pub(crate) fn check_name(name: &str) -> Result<(), CrawlError> {
+    let path = Path::new(name);
+    if name.trim().is_empty()
+        || !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(CrawlError::Config(format!(
+            "name {name:?} must be a relative path of plain components"
+        )));
+    }
+
+    Ok(())
+}
+
+fn claim_id(parent: &Path) -> Result<String, CrawlError> {
+    std::fs::create_dir_all(parent).map_err(CrawlError::io(parent))?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    for attempt in 0..MAX_ID_ATTEMPTS {
+        let id = match attempt {
+            0 => timestamp.to_string(),
+            attempt => format!("{timestamp}-{attempt}"),
+        };
+
+        let directory = parent.join(&id);
+        match std::fs::create_dir(&directory) {
+            Ok(()) => return Ok(id),
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(CrawlError::io(directory)(error)),
+        }
+    }
+
+    Err(CrawlError::Io {
+        path: parent.to_path_buf(),
+        source: std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("no free trajectory directory after {MAX_ID_ATTEMPTS} attempts"),
+        ),
+    })
+}
+
+fn append_line(path: &Path, line: &str) -> Result<(), CrawlError> {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(path)
+        .map_err(CrawlError::io(path))?;
+    writeln!(file, "{line}").map_err(CrawlError::io(path))
+}
+
+// offset the seed so the dynamics do not replay the stream that initialised
+// the lattice. named explicitly for the same reason as
+// `RngSettings::initial_strategies`, recorded histories depend on it
+fn dynamics_rng(grid: &Grid) -> ChaCha8Rng {
+    let seed = grid.rng_settings.as_ref().map_or(0, |rng| rng.seed);
+    ChaCha8Rng::seed_from_u64(seed.wrapping_add(1))
+}
+
+fn neighbour_indices<'a>(
+    grid: &'a Grid,
+    neighbourhood: &'a Neighbourhood,
+    index: usize,
+) -> impl Iterator<Item = usize> + 'a {
+    let (layer, row, col) = grid.get_position(index);
+    neighbourhood
+        .offsets_3d_at(row, col)
+        .filter_map(move |(dx, dy, dz)| grid.get_index_3d(layer + dz, row + dy, col + dx))
+}
+
+fn neighbours_of<'a>(
+    grid: &'a Grid,
+    neighbourhood: &'a Neighbourhood,
+    index: usize,
+) -> impl Iterator<Item = &'a Cell> {
+    neighbour_indices(grid, neighbourhood, index).map(|index| &grid.lattice[index])
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,66 @@
+use rand::RngCore;
+
+// fills `output` in contiguous bands of whole rows, one thread per band, with
+// `fill` receiving the lattice index of the band's first cell
+pub(crate) fn fill_bands<T: Send>(
+    output: &mut [T],
+    num_cols: usize,
+    threads: usize,
+    fill: impl Fn(usize, &mut [T]) + Sync,
+) {
+    let num_rows = output.len().div_ceil(num_cols.max(1));
+    if threads <= 1 || num_rows <= 1 {
+        fill(0, output);
+        return;
+    }
+
+    let band_size = num_rows.div_ceil(threads) * num_cols;
+    std::thread::scope(|scope| {
+        for (band, chunk) in output.chunks_mut(band_size).enumerate() {
+            let fill = &fill;
+            scope.spawn(move || fill(band * band_size, chunk));
+        }
+    });
+}
+
+// splitmix64 stream keyed on the generation and the cell, so a cell draws the
+// same numbers no matter which band or thread updates it
+pub(crate) struct CellRng {
+    state: u64,
+}
+
+impl CellRng {
+    pub(crate) fn new(generation_seed: u64, index: usize) -> Self {
+        let mut rng = Self {
+            state: generation_seed ^ (index as u64).wrapping_mul(0xd1b5_4a32_d192_ed03),
+        };
+        rng.state = rng.next_u64();
+        rng
+    }
+}
+
+impl RngCore for CellRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::{HISTORY_FILE, Trajectory};
+use crate::{CrawlError, config::ExperimentConfig};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReplayReport {
+    pub original_directory: PathBuf,
+    pub replay_directory: PathBuf,
+    // generations present in both the recorded and the regenerated history
+    pub generations_compared: usize,
+    pub first_mismatch: Option<usize>,
+}
+
+impl ReplayReport {
+    #[inline]
+    pub fn is_match(&self) -> bool {
+        self.first_mismatch.is_none()
+    }
+}
+
+// lattice hashes recorded by a trajectory, one hexadecimal hash per line
+pub fn read_history(trajectory_directory: &Path) -> Result<Vec<u64>, CrawlError> {
+    let path = trajectory_directory.join(HISTORY_FILE);
+    std::fs::read_to_string(&path)
+        .map_err(CrawlError::io(&path))?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            u64::from_str_radix(line.trim(), 16).map_err(|_| {
+                CrawlError::Parse(format!("{}: invalid lattice hash {line:?}", path.display()))
+            })
+        })
+        .collect()
+}
+
+impl Trajectory {
+    // reconstructs the trajectory described by a `metadata.json` at
+    // generation zero, it is stored as a new run under the same name
+    pub fn from_metadata(path: impl AsRef<Path>) -> Result<Self, CrawlError> {
+        ExperimentConfig::from_file(path)?.build()
+    }
+
+    // re-runs the trajectory stored in `trajectory_directory` from its
+    // metadata and checks the regenerated lattice hashes against the recorded
+    // history, the replay is stored as a new run beside the original
+    pub fn replay(
+        trajectory_directory: impl AsRef<Path>,
+    ) -> Result<(Self, ReplayReport), CrawlError> {
+        let original_directory = trajectory_directory.as_ref().to_path_buf();
+
+        // read before the replay creates any files of its own
+        let recorded = read_history(&original_directory)?;
+
+        let config = ExperimentConfig::from_file(original_directory.join("metadata.json"))?;
+
+        // the original lives at `output_root/name/id`, and names may span
+        // several path components
+        let mut output_root = original_directory.clone();
+        for _ in 0..Path::new(&config.name).components().count() + 1 {
+            output_root.pop();
+        }
+
+        let mut trajectory = config.build_in(output_root)?;
+        trajectory.run()?;
+
+        let regenerated = trajectory.history();
+        let first_mismatch = recorded
+            .iter()
+            .zip(regenerated)
+            .position(|(recorded, regenerated)| recorded != regenerated)
+            .or_else(|| {
+                (recorded.len() != regenerated.len()).then(|| recorded.len().min(regenerated.len()))
+            });
+
+        let report = ReplayReport {
+            original_directory,
+            replay_directory: trajectory.directory(),
+            generations_compared: recorded.len().min(regenerated.len()),
+            first_mismatch,
+        };
+
+        Ok((trajectory, report))
+    }
+}
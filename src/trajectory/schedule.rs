@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Schedule {
+    // every cell updates at once from the previous generation
+    #[default]
+    Synchronous,
+    // lattice-size many cells are drawn with replacement and updated in turn
+    RandomSequential,
+    // every cell is updated exactly once per generation in a shuffled order
+    RandomPermutation,
+    // every cell carries a unit-rate poisson clock, a generation is one unit
+    // of continuous time
+    PoissonClock,
+}
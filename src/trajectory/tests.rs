@@ -0,0 +1,1551 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rand::RngCore;
+
+use crate::{
+    CrawlError,
+    cell::{BuiltinRule, Cell, UpdateRule},
+    config::ExperimentConfig,
+    grid::{Geometry, Grid, Initialisation, RngSettings},
+    neighbourhood::{CubicShell, Neighbourhood, NeighbourhoodShape},
+    payoff::{Payoff, PayoffMatrix, SpatialPayoff},
+    render::{GIF_FILE, GifOptions},
+    snapshot, stats,
+};
+
+use super::{
+    Cycle, HISTORY_FILE, Schedule, Trajectory, TrajectoryBuilder, birth_death::WeightTree,
+    cycle::CycleDetector, read_history,
+};
+
+fn nowak_may_payoff(b: f32) -> Payoff {
+    Payoff::new(PayoffMatrix::new(1.0, 0.0, 0.0, b))
+}
+
+fn cleanup(trajectory: &Trajectory) {
+    let _ = std::fs::remove_dir_all(trajectory.output_root().join(&trajectory.name));
+}
+
+fn lone_defector(name: &str) -> Trajectory {
+    Trajectory::new(
+        name.to_string(),
+        2,
+        Grid::new((5, 5), true, None).unwrap(),
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_step_lone_defector_invades_moore() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_step_lone_defector_invades_moore".to_string(),
+        1,
+        Grid::new((7, 7), true, None)?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+
+    trajectory.step()?;
+
+    // the lone defector out-scores all of its neighbours and takes over its 3x3 block
+    for (row, col) in trajectory.grid.coordinates() {
+        let cell = trajectory.grid.get_cell(row, col).unwrap();
+        let in_block = (2..=4).contains(&row) && (2..=4).contains(&col);
+        assert_eq!(
+            cell.is_cooperator(),
+            !in_block,
+            "unexpected strategy at ({row}, {col})"
+        );
+    }
+
+    let centre = trajectory.grid.get_cell(3, 3).unwrap();
+    assert!(matches!(centre, Cell::DD(_)));
+    assert!((centre.get_fitness() - 8.0 * 1.9).abs() < 1e-5);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_step_lone_defector_invades_von_neumann() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_step_lone_defector_invades_von_neumann".to_string(),
+        1,
+        Grid::new((7, 7), true, None)?,
+        Neighbourhood::von_neumann(),
+        nowak_may_payoff(1.5),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+
+    trajectory.step()?;
+
+    let defectors: Vec<(i32, i32)> = trajectory
+        .grid
+        .coordinates()
+        .filter(|&(row, col)| !trajectory.grid.get_cell(row, col).unwrap().is_cooperator())
+        .collect();
+    assert_eq!(defectors, vec![(2, 3), (3, 2), (3, 3), (3, 4), (4, 3)]);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_spatial_payoff_reaches_fitness() -> Result<(), CrawlError> {
+    let mut trajectory = Trajectory::builder("test_spatial_payoff_reaches_fitness")
+        .dimension((3, 3))
+        .wrapped(false)
+        .payoff(Payoff::with_spatial(
+            PayoffMatrix::new(1.0, 0.0, 0.0, 1.9),
+            SpatialPayoff::Constant { value: 0.5 },
+        ))
+        .max_iterations(1)
+        .build()?;
+    trajectory.step()?;
+    cleanup(&trajectory);
+
+    // the spatial term is earned once per game: the corner cooperator plays
+    // two cooperators and the central defector, the defector plays all eight
+    let corner = trajectory.grid().get_cell(0, 0).unwrap();
+    assert!((corner.get_fitness() - (2.0 + 3.0 * 0.5)).abs() < 1e-5);
+    let centre = trajectory.grid().get_cell(1, 1).unwrap();
+    assert!((centre.get_fitness() - 8.0 * (1.9 + 0.5)).abs() < 1e-5);
+
+    Ok(())
+}
+
+// positions of the defectors after one step of `builder`, in order
+fn defectors_after_step(builder: TrajectoryBuilder) -> Vec<(i32, i32, i32)> {
+    let mut trajectory = builder.max_iterations(1).build().unwrap();
+    trajectory.step().unwrap();
+    cleanup(&trajectory);
+
+    let grid = trajectory.grid();
+    (0..grid.lattice.len())
+        .filter(|&index| !grid.lattice[index].is_cooperator())
+        .map(|index| grid.get_position(index))
+        .collect()
+}
+
+#[test]
+fn test_self_interaction_shifts_invasion_threshold() {
+    // a cooperator next to the lone defector sees the defector's 8b against
+    // the 8 of a cooperator two steps away, or 9 once cells play themselves,
+    // so the defector spreads above b = 1 without and b = 9/8 with self-play
+    let defectors = |b: f32, include_self: bool| {
+        defectors_after_step(
+            Trajectory::builder("test_self_interaction_shifts_invasion_threshold")
+                .dimension((7, 7))
+                .neighbourhood(Neighbourhood::moore().with_self(include_self))
+                .payoff_matrix(PayoffMatrix::new(1.0, 0.0, 0.0, b)),
+        )
+        .len()
+    };
+    assert_eq!(defectors(1.05, false), 9);
+    assert_eq!(defectors(1.05, true), 1);
+    assert_eq!(defectors(1.12, true), 1);
+    assert_eq!(defectors(1.13, true), 9);
+}
+
+#[test]
+fn test_self_interaction_adds_own_payoff() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::builder("test_self_interaction_adds_own_payoff")
+        .dimension((7, 7))
+        .include_self(true)
+        .max_iterations(1)
+        .build()?;
+    trajectory.step()?;
+
+    // nine cooperators including itself for a cell far from the defector,
+    // nothing extra for the defector since d_d is zero
+    assert_eq!(trajectory.grid.get_cell(0, 0).unwrap().get_fitness(), 9.0);
+    assert!((trajectory.grid.get_cell(3, 3).unwrap().get_fitness() - 8.0 * 1.9).abs() < 1e-5);
+    assert!(trajectory.config().include_self);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_separate_imitation_neighbourhood() {
+    let defectors = |interaction: Neighbourhood, imitation: Neighbourhood| {
+        defectors_after_step(
+            Trajectory::builder("test_separate_imitation_neighbourhood")
+                .dimension((7, 7))
+                .neighbourhood(interaction)
+                .imitation_neighbourhood(imitation),
+        )
+        .len()
+    };
+
+    // the lone defector's diagonal neighbours never play it but still copy it
+    assert_eq!(
+        defectors(Neighbourhood::von_neumann(), Neighbourhood::moore()),
+        9
+    );
+    // the diagonal neighbours play the defector but cannot see it to copy
+    assert_eq!(
+        defectors(Neighbourhood::moore(), Neighbourhood::von_neumann()),
+        5
+    );
+}
+
+#[test]
+fn test_imitation_neighbourhood_is_recorded() -> Result<(), Box<dyn std::error::Error>> {
+    let trajectory = Trajectory::builder("test_imitation_neighbourhood_is_recorded")
+        .dimension((7, 7))
+        .neighbourhood(Neighbourhood::von_neumann())
+        .imitation_neighbourhood(Neighbourhood::moore_radius(2))
+        .max_iterations(1)
+        .build()?;
+
+    let restored = ExperimentConfig::from_file(trajectory.directory().join("metadata.json"))?;
+    assert_eq!(
+        restored.imitation_neighbourhood,
+        Some(NeighbourhoodShape::Moore { radius: 2 })
+    );
+    assert_eq!(restored, trajectory.config());
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_imitation_self_inclusion_is_recorded() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::builder("test_imitation_self_inclusion_is_recorded")
+        .dimension((7, 7))
+        .neighbourhood(Neighbourhood::von_neumann())
+        .imitation_neighbourhood(Neighbourhood::moore().with_self(true))
+        .max_iterations(1)
+        .build()?;
+
+    let config = trajectory.config();
+    assert!(!config.include_self);
+    assert_eq!(config.imitation_include_self, Some(true));
+
+    let restored = ExperimentConfig::from_file(trajectory.directory().join("metadata.json"))?;
+    assert_eq!(restored, config);
+    let rebuilt = restored.build()?;
+    assert_eq!(
+        rebuilt.imitation_neighbourhood,
+        trajectory.imitation_neighbourhood
+    );
+    cleanup(&rebuilt);
+
+    // the public field is recorded as set
+    trajectory.imitation_neighbourhood = Some(Neighbourhood::moore());
+    assert_eq!(trajectory.config().imitation_include_self, None);
+    trajectory.neighbourhood = Neighbourhood::von_neumann().with_self(true);
+    assert_eq!(trajectory.config().imitation_include_self, Some(false));
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_step_lone_defector_invades_other_geometries() {
+    let defectors = |geometry: Geometry, dimension: (i32, i32)| {
+        defectors_after_step(
+            Trajectory::builder("test_step_lone_defector_invades_other_geometries")
+                .dimension(dimension)
+                .geometry(geometry),
+        )
+    };
+
+    // the defector at (3, 3) takes its six axial neighbours
+    assert_eq!(
+        defectors(Geometry::Hexagonal, (7, 7)),
+        [
+            (0, 2, 3),
+            (0, 2, 4),
+            (0, 3, 2),
+            (0, 3, 3),
+            (0, 3, 4),
+            (0, 4, 2),
+            (0, 4, 3)
+        ]
+    );
+
+    // the defector at (3, 0) points down, it takes the cell above and the
+    // cells either side, one of them across the wrapped edge
+    assert_eq!(
+        defectors(Geometry::Triangular, (6, 4)),
+        [(0, 2, 0), (0, 3, 0), (0, 3, 1), (0, 3, 3)]
+    );
+}
+
+#[test]
+fn test_geometry_mismatch_is_rejected() {
+    let result = Trajectory::builder("test_geometry_mismatch_is_rejected")
+        .geometry(Geometry::Hexagonal)
+        .neighbourhood(Neighbourhood::moore())
+        .build();
+    assert!(matches!(result, Err(CrawlError::Config(_))));
+
+    let result = Trajectory::new(
+        "test_geometry_mismatch_is_rejected".to_string(),
+        1,
+        Grid::new((5, 5), true, None).unwrap(),
+        Neighbourhood::triangular(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    );
+    assert!(matches!(result, Err(CrawlError::Config(_))));
+    assert!(!std::path::Path::new("trajectories/test_geometry_mismatch_is_rejected").exists());
+}
+
+#[test]
+fn test_zero_temperature_is_rejected() {
+    let result = Trajectory::new(
+        "test_zero_temperature_is_rejected".to_string(),
+        1,
+        Grid::new((5, 5), true, None).unwrap(),
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::Fermi { temperature: 0.0 },
+        Schedule::Synchronous,
+    );
+    assert!(matches!(result, Err(CrawlError::Config(_))));
+    assert!(!std::path::Path::new("trajectories/test_zero_temperature_is_rejected").exists());
+}
+
+#[test]
+fn test_step_all_cooperators_is_fixed_point() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_step_all_cooperators_is_fixed_point".to_string(),
+        3,
+        Grid::new((10, 10), true, Some(RngSettings::new(Some(0), 1.0)?))?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+
+    trajectory.run()?;
+
+    assert!(
+        trajectory
+            .grid
+            .lattice
+            .iter()
+            .all(|cell| cell.is_cooperator())
+    );
+    assert!(
+        trajectory
+            .grid
+            .lattice
+            .iter()
+            .all(|cell| cell.get_fitness() == 8.0)
+    );
+    assert!(trajectory.history.windows(2).all(|w| w[0] == w[1]));
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_step_non_wrapped_edges() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_step_non_wrapped_edges".to_string(),
+        1,
+        Grid::new((3, 3), false, Some(RngSettings::new(Some(0), 1.0)?))?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+
+    trajectory.step()?;
+
+    assert_eq!(trajectory.grid.get_cell(0, 0).unwrap().get_fitness(), 3.0);
+    assert_eq!(trajectory.grid.get_cell(0, 1).unwrap().get_fitness(), 5.0);
+    assert_eq!(trajectory.grid.get_cell(1, 1).unwrap().get_fitness(), 8.0);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_run_stops_at_max_iterations() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_run_stops_at_max_iterations".to_string(),
+        5,
+        Grid::new((5, 5), true, None)?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+
+    trajectory.run()?;
+    assert_eq!(trajectory.curr_iteration, 5);
+    assert_eq!(trajectory.history.len(), 6);
+
+    // further runs are a no-op once the budget is spent
+    trajectory.run()?;
+    assert_eq!(trajectory.curr_iteration, 5);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_stochastic_rule_is_reproducible() -> Result<(), Box<dyn std::error::Error>> {
+    let build = |name: &str| {
+        Trajectory::new(
+            name.to_string(),
+            10,
+            Grid::new((20, 20), true, Some(RngSettings::new(Some(7), 0.5)?))?,
+            Neighbourhood::moore(),
+            nowak_may_payoff(1.6),
+            BuiltinRule::Fermi { temperature: 0.1 },
+            Schedule::Synchronous,
+        )
+    };
+
+    let mut first = build("test_stochastic_rule_is_reproducible_a")?;
+    let mut second = build("test_stochastic_rule_is_reproducible_b")?;
+    first.run()?;
+    second.run()?;
+
+    assert_eq!(first.history, second.history);
+
+    cleanup(&first);
+    cleanup(&second);
+    Ok(())
+}
+
+#[test]
+fn test_step_with_custom_rule() -> Result<(), Box<dyn std::error::Error>> {
+    struct AlwaysDefect;
+
+    impl UpdateRule for AlwaysDefect {
+        fn next_strategy(&self, _: &Cell, _: &[&Cell], _: &mut dyn RngCore) -> bool {
+            false
+        }
+    }
+
+    let mut trajectory = Trajectory::new(
+        "test_step_with_custom_rule".to_string(),
+        1,
+        Grid::new((5, 5), true, Some(RngSettings::new(Some(0), 1.0)?))?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+
+    trajectory.step_with(&AlwaysDefect)?;
+    assert!(
+        trajectory
+            .grid
+            .lattice
+            .iter()
+            .all(|cell| *cell == Cell::CD(8.0))
+    );
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_metadata_records_update_rule() -> Result<(), Box<dyn std::error::Error>> {
+    let trajectory = Trajectory::new(
+        "test_metadata_records_update_rule".to_string(),
+        1,
+        Grid::new((5, 5), true, None)?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::Fermi { temperature: 0.5 },
+        Schedule::RandomPermutation,
+    )?;
+
+    let metadata: serde_json::Value = serde_json::from_str(&trajectory.serialize_metadata()?)?;
+    assert_eq!(
+        metadata["update_rule"],
+        serde_json::json!({ "Fermi": { "temperature": 0.5 } })
+    );
+    assert_eq!(metadata["schedule"], "RandomPermutation");
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+struct CountingRule {
+    calls: AtomicUsize,
+}
+
+impl UpdateRule for CountingRule {
+    fn next_strategy(&self, cell: &Cell, _: &[&Cell], _: &mut dyn RngCore) -> bool {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        cell.is_cooperator()
+    }
+}
+
+fn count_updates(name: &str, schedule: Schedule, generations: usize) -> usize {
+    let mut trajectory = Trajectory::new(
+        name.to_string(),
+        generations,
+        Grid::new((10, 10), true, None).unwrap(),
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        schedule,
+    )
+    .unwrap();
+
+    let rule = CountingRule {
+        calls: AtomicUsize::new(0),
+    };
+    for _ in 0..generations {
+        trajectory.step_with(&rule).unwrap();
+    }
+
+    cleanup(&trajectory);
+    rule.calls.into_inner()
+}
+
+#[test]
+fn test_schedule_update_counts() {
+    assert_eq!(
+        count_updates("test_schedule_update_counts_sync", Schedule::Synchronous, 3),
+        300
+    );
+    assert_eq!(
+        count_updates(
+            "test_schedule_update_counts_sequential",
+            Schedule::RandomSequential,
+            3
+        ),
+        300
+    );
+    assert_eq!(
+        count_updates(
+            "test_schedule_update_counts_permutation",
+            Schedule::RandomPermutation,
+            3
+        ),
+        300
+    );
+
+    // poisson clocks only fire lattice-size many times on average
+    let updates = count_updates(
+        "test_schedule_update_counts_poisson",
+        Schedule::PoissonClock,
+        100,
+    );
+    assert!((9_500..10_500).contains(&updates), "{updates} updates");
+}
+
+#[test]
+fn test_asynchronous_all_cooperators_is_fixed_point() -> Result<(), Box<dyn std::error::Error>> {
+    for (index, schedule) in [
+        Schedule::RandomSequential,
+        Schedule::RandomPermutation,
+        Schedule::PoissonClock,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let mut trajectory = Trajectory::new(
+            format!("test_asynchronous_all_cooperators_is_fixed_point_{index}"),
+            3,
+            Grid::new((6, 6), true, Some(RngSettings::new(Some(0), 1.0)?))?,
+            Neighbourhood::moore(),
+            nowak_may_payoff(1.9),
+            BuiltinRule::Fermi { temperature: 0.1 },
+            schedule,
+        )?;
+
+        trajectory.run()?;
+
+        assert!(
+            trajectory
+                .grid
+                .lattice
+                .iter()
+                .all(|cell| *cell == Cell::CC(8.0)),
+            "{schedule:?} left the all-cooperator state"
+        );
+
+        cleanup(&trajectory);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_asynchronous_schedules_are_reproducible() -> Result<(), Box<dyn std::error::Error>> {
+    for (index, schedule) in [
+        Schedule::RandomSequential,
+        Schedule::RandomPermutation,
+        Schedule::PoissonClock,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let build = |suffix: &str| {
+            Trajectory::new(
+                format!("test_asynchronous_schedules_are_reproducible_{index}_{suffix}"),
+                5,
+                Grid::new((16, 16), true, Some(RngSettings::new(Some(3), 0.7)?))?,
+                Neighbourhood::moore(),
+                nowak_may_payoff(1.7),
+                BuiltinRule::Fermi { temperature: 0.2 },
+                schedule,
+            )
+        };
+
+        let mut first = build("a")?;
+        let mut second = build("b")?;
+        first.run()?;
+        second.run()?;
+
+        assert_eq!(first.history, second.history, "{schedule:?} diverged");
+
+        cleanup(&first);
+        cleanup(&second);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_asynchronous_transitions_match_strategy_changes() -> Result<(), Box<dyn std::error::Error>>
+{
+    let mut trajectory = Trajectory::new(
+        "test_asynchronous_transitions_match_strategy_changes".to_string(),
+        20,
+        Grid::new((20, 20), true, Some(RngSettings::new(Some(5), 0.6)?))?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.7),
+        BuiltinRule::Fermi { temperature: 0.2 },
+        Schedule::RandomSequential,
+    )?;
+
+    let mut changes = 0;
+    for _ in 0..20 {
+        let before: Vec<bool> = trajectory
+            .grid
+            .lattice
+            .iter()
+            .map(Cell::is_cooperator)
+            .collect();
+        trajectory.step()?;
+
+        // cells drawn twice or not at all are marked by the generation as a
+        // whole, not by their last update
+        for (cell, was_cooperator) in trajectory.grid.lattice.iter().zip(before) {
+            let marked_was_cooperator = matches!(cell, Cell::CC(_) | Cell::CD(_));
+            assert_eq!(marked_was_cooperator, was_cooperator);
+            changes += usize::from(was_cooperator != cell.is_cooperator());
+        }
+    }
+    assert!(changes > 0);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+fn stochastic_history(name: &str, schedule: Schedule) -> Vec<u64> {
+    let mut trajectory = Trajectory::new(
+        name.to_string(),
+        3,
+        Grid::new((8, 8), true, Some(RngSettings::new(Some(42), 0.7).unwrap())).unwrap(),
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.7),
+        BuiltinRule::Fermi { temperature: 0.2 },
+        schedule,
+    )
+    .unwrap();
+    trajectory.run().unwrap();
+    cleanup(&trajectory);
+    trajectory.history
+}
+
+#[test]
+fn test_dynamics_are_pinned() {
+    // stochastic rules and asynchronous schedules draw from the dynamics
+    // stream, a change here breaks replaying recorded histories
+    let synchronous = stochastic_history("test_dynamics_are_pinned_sync", Schedule::Synchronous);
+    let sequential = stochastic_history(
+        "test_dynamics_are_pinned_sequential",
+        Schedule::RandomSequential,
+    );
+    assert_eq!(
+        synchronous,
+        [
+            0x55b8_a258_97da_ba85,
+            0x82c5_c5ed_7474_4e67,
+            0x6655_5021_f5a6_629e,
+            0x02e2_373a_055a_3565
+        ]
+    );
+    assert_eq!(
+        sequential,
+        [
+            0x55b8_a258_97da_ba85,
+            0xf1c2_044d_6335_0e9a,
+            0xbaa7_0b8b_054d_9da7,
+            0x1b6e_efd2_c740_1c3d
+        ]
+    );
+}
+
+#[test]
+fn test_cycle_detector_fixed_point_and_period() {
+    let mut detector = CycleDetector::default();
+    detector.observe(0, 1, None);
+    detector.observe(1, 2, None);
+    detector.observe(2, 3, None);
+    assert_eq!(detector.cycle(), None);
+    assert_eq!(detector.candidates(2), [1]);
+
+    detector.observe(3, 2, Some(1));
+    assert_eq!(
+        detector.cycle(),
+        Some(Cycle {
+            transient: 1,
+            period: 2
+        })
+    );
+
+    // later recurrences do not overwrite the first cycle found, and nothing
+    // is kept once it is known
+    assert!(detector.candidates(3).is_empty());
+    detector.observe(4, 3, Some(2));
+    assert_eq!(detector.cycle().unwrap().transient, 1);
+}
+
+#[test]
+fn test_cycle_detector_keeps_unconfirmed_candidates() {
+    // a candidate the caller found to hold another lattice stays a candidate
+    let mut detector = CycleDetector::default();
+    detector.observe(0, 42, None);
+    detector.observe(1, 42, None);
+    assert_eq!(detector.cycle(), None);
+    assert_eq!(detector.candidates(42), [0, 1]);
+
+    detector.observe(2, 42, Some(1));
+    assert_eq!(
+        detector.cycle(),
+        Some(Cycle {
+            transient: 1,
+            period: 1
+        })
+    );
+}
+
+#[test]
+fn test_first_recurrence_compares_lattices() -> Result<(), Box<dyn std::error::Error>> {
+    let rule = BuiltinRule::ImitateBest;
+
+    let mut invaded = lone_defector("test_first_recurrence_compares_lattices_invaded");
+    invaded.step()?;
+    invaded.step()?;
+    assert_eq!(invaded.first_recurrence(&rule, &[0, 1])?, None);
+    cleanup(&invaded);
+
+    let mut fixed = Trajectory::new(
+        "test_first_recurrence_compares_lattices_fixed".to_string(),
+        2,
+        Grid::new((6, 6), true, Some(RngSettings::new(Some(0), 1.0)?))?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        rule,
+        Schedule::Synchronous,
+    )?;
+    fixed.step()?;
+    fixed.step()?;
+    assert_eq!(fixed.first_recurrence(&rule, &[0, 1])?, Some(0));
+    cleanup(&fixed);
+    Ok(())
+}
+
+#[test]
+fn test_run_detects_fixed_point() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_run_detects_fixed_point".to_string(),
+        10,
+        Grid::new((6, 6), true, Some(RngSettings::new(Some(0), 1.0)?))?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+    trajectory.detect_cycles = true;
+
+    trajectory.run()?;
+    assert_eq!(trajectory.curr_iteration, 10);
+
+    let cycle = trajectory.cycle().unwrap();
+    assert!(cycle.is_fixed_point());
+    assert_eq!(cycle.transient, 0);
+
+    let summary: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(
+        trajectory.directory().join("cycle.json"),
+    )?)?;
+    assert_eq!(summary["generations"], 10);
+    assert_eq!(summary["fixed_point"], true);
+    assert_eq!(summary["cycle"]["period"], 1);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_hash_collision_does_not_stop_the_run() -> Result<(), CrawlError> {
+    let builder = |name: &str| {
+        Trajectory::builder(name)
+            .dimension((21, 21))
+            .payoff_matrix(PayoffMatrix::new(1.0, 0.0, 0.0, 1.9))
+            .stop_on_cycle(true)
+            .max_iterations(4)
+    };
+
+    let mut ahead = builder("test_hash_collision_does_not_stop_the_run_ahead").build()?;
+    ahead.step()?;
+    ahead.step()?;
+    cleanup(&ahead);
+
+    // pretend generation zero hashed like generation two, so the growing
+    // block of defectors appears to revisit the lone defector
+    let mut trajectory = builder("test_hash_collision_does_not_stop_the_run").build()?;
+    trajectory.step()?;
+    trajectory
+        .cycle_detector
+        .observe(0, ahead.history()[2], None);
+    trajectory.run()?;
+    cleanup(&trajectory);
+
+    assert_eq!(trajectory.cycle(), None);
+    assert_eq!(trajectory.generation(), 4);
+    assert_eq!(&trajectory.history()[..3], &ahead.history()[..3]);
+    Ok(())
+}
+
+#[test]
+fn test_cycles_are_not_confirmed_once_dynamics_change() -> Result<(), CrawlError> {
+    let mut trajectory = lone_defector("test_cycles_are_not_confirmed_once_dynamics_change");
+    trajectory.detect_cycles = true;
+    trajectory.max_iterations = 5;
+
+    trajectory.step()?;
+    trajectory.imitation_neighbourhood = Some(Neighbourhood::von_neumann());
+    trajectory.step()?;
+    trajectory.imitation_neighbourhood = None;
+    trajectory.run()?;
+    cleanup(&trajectory);
+
+    // the defectors settle, but a replay with one imitation neighbourhood
+    // could not vouch for every generation
+    let history = trajectory.history();
+    assert_eq!(history[4], history[5]);
+    assert_eq!(trajectory.cycle(), None);
+    Ok(())
+}
+
+#[test]
+fn test_cycles_are_only_detected_on_request() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_cycles_are_only_detected_on_request".to_string(),
+        3,
+        Grid::new((6, 6), true, Some(RngSettings::new(Some(0), 1.0)?))?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+
+    trajectory.run()?;
+    assert_eq!(trajectory.cycle(), None);
+    assert!(!trajectory.directory().join("cycle.json").exists());
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_run_stops_on_cycle() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_run_stops_on_cycle".to_string(),
+        100,
+        Grid::new((9, 9), true, None)?,
+        Neighbourhood::von_neumann(),
+        nowak_may_payoff(1.5),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+    trajectory.stop_on_cycle = true;
+
+    trajectory.run()?;
+
+    let cycle = trajectory.cycle().unwrap();
+    assert!(trajectory.curr_iteration < 100);
+    assert_eq!(trajectory.curr_iteration, cycle.transient + cycle.period);
+    assert_eq!(
+        trajectory.history[cycle.transient],
+        trajectory.history[trajectory.curr_iteration]
+    );
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_run_writes_snapshots() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_run_writes_snapshots".to_string(),
+        5,
+        Grid::new((12, 12), true, Some(RngSettings::new(Some(11), 0.8)?))?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.7),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+    trajectory.snapshot_interval = Some(2);
+
+    trajectory.run()?;
+
+    let directory = trajectory.directory();
+    assert_eq!(
+        snapshot::snapshot_generations(&directory)?,
+        vec![0, 2, 4, 5]
+    );
+    for generation in [0, 2, 4, 5] {
+        let grid = snapshot::load_grid(&directory, generation)?;
+        assert_eq!(grid.get_lattice_hash(), trajectory.history[generation]);
+    }
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_run_writes_statistics() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_run_writes_statistics".to_string(),
+        4,
+        Grid::new((7, 7), true, None)?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+
+    trajectory.run()?;
+
+    let statistics = stats::read_statistics(&trajectory.directory())?;
+    assert_eq!(statistics.len(), 5);
+    assert_eq!(statistics[0].generation, 0);
+    assert_eq!(statistics[0].dd, 1);
+
+    // after one generation the lone defector owns its 3x3 block
+    assert_eq!(
+        (
+            statistics[1].cc,
+            statistics[1].cd,
+            statistics[1].dd,
+            statistics[1].dc
+        ),
+        (40, 8, 1, 0)
+    );
+    assert_eq!(statistics[1].strategy_changes, 8);
+    assert_eq!(statistics[1].min_fitness, 7.0);
+    assert!((statistics[1].max_fitness - 8.0 * 1.9).abs() < 1e-5);
+    assert_eq!(statistics.last(), Some(&trajectory.statistics()));
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_run_writes_gif() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_run_writes_gif".to_string(),
+        5,
+        Grid::new((9, 9), true, None)?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+    trajectory.gif = Some(GifOptions {
+        stride: 2,
+        ..GifOptions::default()
+    });
+
+    trajectory.run()?;
+
+    // generations 0, 2, 4 and the final generation 5
+    let file = std::fs::File::open(trajectory.directory().join(GIF_FILE))?;
+    let mut decoder = gif::DecodeOptions::new().read_info(file)?;
+    let mut frames = 0;
+    while decoder.read_next_frame()?.is_some() {
+        frames += 1;
+    }
+    assert_eq!(frames, 4);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_run_writes_history() -> Result<(), Box<dyn std::error::Error>> {
+    let mut trajectory = Trajectory::new(
+        "test_run_writes_history".to_string(),
+        4,
+        Grid::new((9, 9), true, None)?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+
+    trajectory.run()?;
+
+    assert_eq!(read_history(&trajectory.directory())?, trajectory.history());
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_replay_matches_recorded_history() -> Result<(), Box<dyn std::error::Error>> {
+    let mut original = Trajectory::new(
+        "test_replay_matches_recorded_history".to_string(),
+        8,
+        Grid::new((16, 16), true, Some(RngSettings::new(Some(5), 0.75)?))?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.7),
+        BuiltinRule::Fermi { temperature: 0.3 },
+        Schedule::RandomSequential,
+    )?;
+    original.run()?;
+
+    let (replayed, report) = Trajectory::replay(original.directory())?;
+
+    assert!(report.is_match());
+    assert_eq!(report.generations_compared, 9);
+    assert_eq!(replayed.history(), original.history());
+    assert_eq!(original.config().initial_snapshot, None);
+
+    cleanup(&original);
+    Ok(())
+}
+
+#[test]
+fn test_replay_starts_from_an_edited_grid() -> Result<(), Box<dyn std::error::Error>> {
+    let mut grid = Grid::new((6, 6), true, Some(RngSettings::new(Some(3), 0.8)?))?;
+    grid.lattice[7] = Cell::new(!grid.lattice[7].is_cooperator());
+
+    let mut original = Trajectory::new(
+        "test_replay_starts_from_an_edited_grid".to_string(),
+        4,
+        grid,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+    original.run()?;
+
+    // the rng settings no longer describe the lattice, so it is kept
+    let initial_snapshot = original.config().initial_snapshot.unwrap();
+    assert_eq!(
+        initial_snapshot,
+        std::path::absolute(original.directory().join(snapshot::INITIAL_SNAPSHOT_FILE))?
+    );
+
+    let (replayed, report) = Trajectory::replay(original.directory())?;
+    assert!(report.is_match());
+    assert_eq!(replayed.history(), original.history());
+
+    cleanup(&original);
+    Ok(())
+}
+
+#[test]
+fn test_replay_reports_first_mismatch() -> Result<(), Box<dyn std::error::Error>> {
+    let mut original = Trajectory::new(
+        "test_replay_reports_first_mismatch".to_string(),
+        5,
+        Grid::new((9, 9), true, None)?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+    original.run()?;
+
+    // tamper with the record of generation 3
+    let path = original.directory().join(HISTORY_FILE);
+    let mut lines: Vec<String> = std::fs::read_to_string(&path)?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    lines[3] = format!("{:016x}", original.history()[3] ^ 1);
+    std::fs::write(&path, lines.join("\n"))?;
+
+    let (_, report) = Trajectory::replay(original.directory())?;
+    assert!(!report.is_match());
+    assert_eq!(report.first_mismatch, Some(3));
+
+    cleanup(&original);
+    Ok(())
+}
+
+#[test]
+fn test_names_stay_below_the_output_root() {
+    let output_root = std::env::temp_dir().join("crawl_test_names_stay_below_the_output_root");
+
+    for name in ["../escape", "a/../../escape", "/escape", "./escape", ""] {
+        let result = Trajectory::builder(name).output_root(&output_root).build();
+        assert!(
+            matches!(result, Err(CrawlError::Config(_))),
+            "{name:?} was accepted"
+        );
+    }
+    let result = Trajectory::new_in(
+        &output_root,
+        "../escape".to_string(),
+        1,
+        Grid::new((5, 5), true, None).unwrap(),
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    );
+    assert!(matches!(result, Err(CrawlError::Config(_))));
+    assert!(!std::env::temp_dir().join("escape").exists());
+
+    let trajectory = Trajectory::builder("nested/name")
+        .output_root(&output_root)
+        .build()
+        .unwrap();
+    assert!(
+        trajectory
+            .directory()
+            .starts_with(output_root.join("nested/name"))
+    );
+
+    let _ = std::fs::remove_dir_all(&output_root);
+}
+
+#[test]
+fn test_same_name_runs_get_distinct_directories() -> Result<(), Box<dyn std::error::Error>> {
+    let trajectories: Vec<_> = (0..5)
+        .map(|_| lone_defector("test_same_name_runs_get_distinct_directories"))
+        .collect();
+
+    let mut directories: Vec<_> = trajectories.iter().map(Trajectory::directory).collect();
+    directories.sort();
+    directories.dedup();
+    assert_eq!(directories.len(), 5);
+
+    for directory in &directories {
+        assert!(directory.join("metadata.json").exists());
+    }
+
+    cleanup(&trajectories[0]);
+    Ok(())
+}
+
+#[test]
+fn test_existing_directory_is_not_reused() -> Result<(), Box<dyn std::error::Error>> {
+    let first = lone_defector("test_existing_directory_is_not_reused");
+    let marker = first.directory().join("marker");
+    std::fs::write(&marker, "untouched")?;
+
+    let second = lone_defector("test_existing_directory_is_not_reused");
+    assert_ne!(first.directory(), second.directory());
+    assert_eq!(std::fs::read_to_string(&marker)?, "untouched");
+
+    cleanup(&first);
+    Ok(())
+}
+
+#[test]
+fn test_new_in_custom_output_root() -> Result<(), Box<dyn std::error::Error>> {
+    let output_root = std::env::temp_dir().join("crawl_test_new_in_custom_output_root");
+    let _ = std::fs::remove_dir_all(&output_root);
+
+    let mut original = Trajectory::new_in(
+        &output_root,
+        "parent/child".to_string(),
+        4,
+        Grid::new((9, 9), true, None)?,
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.9),
+        BuiltinRule::ImitateBest,
+        Schedule::Synchronous,
+    )?;
+    original.run()?;
+
+    assert!(
+        original
+            .directory()
+            .starts_with(output_root.join("parent/child"))
+    );
+    assert!(original.directory().join(HISTORY_FILE).exists());
+    assert!(!std::path::Path::new("trajectories/parent/child").exists());
+
+    // the replay lands beside the original even for nested names
+    let (replayed, report) = Trajectory::replay(original.directory())?;
+    assert!(report.is_match());
+    assert_eq!(replayed.output_root(), output_root);
+    assert_ne!(replayed.directory(), original.directory());
+
+    std::fs::remove_dir_all(&output_root)?;
+    Ok(())
+}
+
+fn threaded_history(name: &str, rule: BuiltinRule, wrapped: bool, threads: usize) -> Vec<u64> {
+    let mut trajectory = Trajectory::new(
+        name.to_string(),
+        8,
+        Grid::new(
+            (13, 11),
+            wrapped,
+            Some(RngSettings::new(Some(5), 0.6).unwrap()),
+        )
+        .unwrap(),
+        Neighbourhood::moore(),
+        nowak_may_payoff(1.6),
+        rule,
+        Schedule::Synchronous,
+    )
+    .unwrap();
+    trajectory.threads = threads;
+
+    // a lone defector keeps the deterministic rules from settling immediately
+    trajectory
+        .grid
+        .lattice
+        .iter_mut()
+        .for_each(|cell| cell.update_strategy(true));
+    trajectory.grid.lattice[60].update_strategy(false);
+
+    trajectory.run().unwrap();
+    cleanup(&trajectory);
+    trajectory.history().to_vec()
+}
+
+#[test]
+fn test_weight_tree_samples_by_weight() {
+    let mut weights = WeightTree::new(vec![0.0, 2.0, 0.0, 1.0, 3.0]);
+    assert_eq!(weights.total(), 6.0);
+
+    let picks: Vec<usize> = [0.0, 1.9, 2.0, 2.9, 3.0, 5.9]
+        .into_iter()
+        .map(|target| weights.sample(target))
+        .collect();
+    assert_eq!(picks, [1, 1, 3, 3, 4, 4]);
+    // a target rounded up to the total still lands on a weighted index
+    assert_eq!(weights.sample(6.0), 4);
+
+    weights.set(4, 0.0);
+    weights.set(0, 1.0);
+    assert_eq!(weights.total(), 4.0);
+    assert_eq!(weights.sample(0.5), 0);
+    assert_eq!(weights.sample(3.5), 3);
+    assert_eq!(weights.sample(4.0), 3);
+}
+
+#[test]
+fn test_birth_death_parents_need_fitness() -> Result<(), CrawlError> {
+    // defectors score nothing, so only cooperators reproduce and the
+    // defectors can only lose ground
+    let mut trajectory = Trajectory::builder("test_birth_death_parents_need_fitness")
+        .dimension((20, 20))
+        .cooperator_frequency(0.5)
+        .seed(4)
+        .payoff_matrix(PayoffMatrix::new(1.0, 0.0, 0.0, 0.0))
+        .update_rule(BuiltinRule::BirthDeath)
+        .max_iterations(10)
+        .build()?;
+
+    let defectors = |trajectory: &Trajectory| {
+        let lattice = &trajectory.grid().lattice;
+        lattice.iter().filter(|cell| !cell.is_cooperator()).count()
+    };
+
+    let initial = defectors(&trajectory);
+    let mut previous = initial;
+    for _ in 0..10 {
+        trajectory.step()?;
+        let current = defectors(&trajectory);
+        assert!(current <= previous);
+        previous = current;
+    }
+    cleanup(&trajectory);
+
+    assert!(previous < initial);
+    Ok(())
+}
+
+#[test]
+fn test_birth_death_transitions_match_strategy_changes() -> Result<(), CrawlError> {
+    let mut trajectory = Trajectory::builder("test_birth_death_transitions")
+        .dimension((10, 10))
+        .cooperator_frequency(0.6)
+        .seed(8)
+        .update_rule(BuiltinRule::BirthDeath)
+        .max_iterations(1)
+        .build()?;
+
+    let start: Vec<bool> = trajectory
+        .grid()
+        .lattice
+        .iter()
+        .map(Cell::is_cooperator)
+        .collect();
+    trajectory.step()?;
+    cleanup(&trajectory);
+
+    for (cell, was_cooperator) in trajectory.grid().lattice.iter().zip(start) {
+        let expected = match (was_cooperator, cell.is_cooperator()) {
+            (true, true) => Cell::CC(0.0),
+            (true, false) => Cell::CD(0.0),
+            (false, false) => Cell::DD(0.0),
+            (false, true) => Cell::DC(0.0),
+        };
+        assert_eq!(cell.to_code(), expected.to_code());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_threaded_step_matches_serial() {
+    for (label, rule) in [
+        ("imitate_best", BuiltinRule::ImitateBest),
+        ("fermi", BuiltinRule::Fermi { temperature: 0.5 }),
+        ("death_birth", BuiltinRule::DeathBirth),
+        ("birth_death", BuiltinRule::BirthDeath),
+    ] {
+        for wrapped in [true, false] {
+            let serial = threaded_history(
+                &format!("test_threaded_step_{label}_{wrapped}_1"),
+                rule,
+                wrapped,
+                1,
+            );
+            assert!(serial.windows(2).any(|pair| pair[0] != pair[1]));
+
+            // band counts that do and do not divide the 13 rows
+            for threads in [2, 4, 13, 32] {
+                let threaded = threaded_history(
+                    &format!("test_threaded_step_{label}_{wrapped}_{threads}"),
+                    rule,
+                    wrapped,
+                    threads,
+                );
+                assert_eq!(serial, threaded, "{label} diverges with {threads} threads");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_builder_defaults() -> Result<(), CrawlError> {
+    // the prelude is all a downstream crate needs
+    let mut trajectory = crate::prelude::Trajectory::builder("test_builder_defaults")
+        .dimension((9, 9))
+        .max_iterations(3)
+        .build()?;
+
+    let config = trajectory.config();
+    assert_eq!(config.grid.dimension, (9, 9));
+    assert!(config.grid.wrapped);
+    assert_eq!(config.grid.rng_settings, None);
+    assert_eq!(config.neighbourhood, *Neighbourhood::moore().shape());
+    assert_eq!(
+        config.payoff,
+        Payoff::new(PayoffMatrix::new(1.0, 0.0, 0.0, 1.9))
+    );
+    assert_eq!(config.update_rule, BuiltinRule::ImitateBest);
+    assert_eq!(config.schedule, Schedule::Synchronous);
+
+    trajectory.run()?;
+    assert_eq!(trajectory.statistics().generation, 3);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_builder_matches_config() -> Result<(), CrawlError> {
+    let mut built = Trajectory::builder("test_builder_matches_config")
+        .dimension((12, 10))
+        .wrapped(false)
+        .cooperator_frequency(0.7)
+        .seed(11)
+        .initialisation(Initialisation::ExactCount)
+        .neighbourhood(Neighbourhood::von_neumann())
+        .payoff_matrix(PayoffMatrix::new(1.0, 0.0, 0.0, 1.4))
+        .spatial_payoff(SpatialPayoff::Constant { value: 0.1 })
+        .update_rule(BuiltinRule::Fermi { temperature: 0.2 })
+        .schedule(Schedule::RandomPermutation)
+        .max_iterations(6)
+        .snapshot_interval(2)
+        .threads(3)
+        .build()?;
+    built.run()?;
+
+    let config = built.config();
+    assert_eq!(config.grid.rng_settings.as_ref().unwrap().seed, 11);
+    assert_eq!(config.snapshot_interval, Some(2));
+
+    let mut from_config = config.build()?;
+    from_config.run()?;
+    assert_eq!(from_config.history(), built.history());
+
+    cleanup(&built);
+    Ok(())
+}
+
+#[test]
+fn test_builder_with_grid() -> Result<(), CrawlError> {
+    let mut grid = Grid::new((6, 8), false, None)?;
+    grid.get_cell_mut(0, 0).unwrap().update_strategy(false);
+
+    let trajectory = Trajectory::builder("test_builder_with_grid")
+        .grid(grid)
+        // ignored in favour of the grid
+        .dimension((100, 100))
+        .build()?;
+
+    let config = trajectory.config();
+    assert_eq!(config.grid.dimension, (6, 8));
+    assert!(!config.grid.wrapped);
+    assert_eq!(trajectory.statistics().dd + trajectory.statistics().cd, 2);
+
+    cleanup(&trajectory);
+    Ok(())
+}
+
+#[test]
+fn test_builder_with_snapshot_grid_replays() -> Result<(), CrawlError> {
+    let mut original = Trajectory::builder("test_builder_with_snapshot_grid_replays_original")
+        .dimension((10, 10))
+        .cooperator_frequency(0.7)
+        .seed(6)
+        .update_rule(BuiltinRule::Fermi { temperature: 0.3 })
+        .schedule(Schedule::RandomSequential)
+        .snapshot_interval(2)
+        .max_iterations(2)
+        .build()?;
+    original.run()?;
+
+    // generation two of the old run, which its rng settings do not describe
+    let grid = snapshot::load_grid(&original.directory(), 2)?;
+    let mut resumed = Trajectory::builder("test_builder_with_snapshot_grid_replays")
+        .grid(grid)
+        .update_rule(BuiltinRule::Fermi { temperature: 0.3 })
+        .schedule(Schedule::RandomSequential)
+        .max_iterations(5)
+        .build()?;
+    resumed.run()?;
+    assert!(resumed.config().initial_snapshot.is_some());
+
+    let (replayed, report) = Trajectory::replay(resumed.directory())?;
+    assert!(report.is_match());
+    assert_eq!(replayed.history(), resumed.history());
+    assert_eq!(resumed.history()[0], original.history()[2]);
+
+    cleanup(&original);
+    cleanup(&resumed);
+    Ok(())
+}
+
+#[test]
+fn test_builder_validates_before_writing() {
+    let directory = std::path::Path::new("trajectories/test_builder_validates_before_writing");
+
+    let result = Trajectory::builder("test_builder_validates_before_writing")
+        .seed(3)
+        .build();
+    assert!(matches!(result, Err(CrawlError::Config(_))));
+
+    let result = Trajectory::builder("test_builder_validates_before_writing")
+        .cooperator_frequency(1.5)
+        .build();
+    assert!(matches!(result, Err(CrawlError::InvalidFrequency(_))));
+
+    let result = Trajectory::builder("test_builder_validates_before_writing")
+        .dimension((0, 3))
+        .build();
+    assert!(matches!(result, Err(CrawlError::InvalidDimension(_))));
+
+    assert!(!directory.exists());
+}
+
+#[test]
+fn test_lattice_is_readable_through_the_prelude() -> Result<(), CrawlError> {
+    use crate::prelude::*;
+
+    let mut trajectory = Trajectory::builder("test_lattice_is_readable_through_the_prelude")
+        .dimension((5, 5))
+        .max_iterations(1)
+        .build()?;
+    assert_eq!(trajectory.generation(), 0);
+    trajectory.run()?;
+    cleanup(&trajectory);
+
+    let grid: &Grid = trajectory.grid();
+    let defectors = grid
+        .coordinates()
+        .filter(|&(row, col)| !grid.get_cell(row, col).unwrap().is_cooperator())
+        .count();
+    assert_eq!(trajectory.generation(), 1);
+    assert_eq!(defectors, 9);
+    Ok(())
+}
+
+#[test]
+fn test_builder_output_root() -> Result<(), CrawlError> {
+    let output_root = std::env::temp_dir().join("crawl_test_builder_output_root");
+    let _ = std::fs::remove_dir_all(&output_root);
+
+    let trajectory = Trajectory::builder("test_builder_output_root")
+        .dimension((5, 5))
+        .output_root(&output_root)
+        .build()?;
+    assert!(trajectory.directory().starts_with(&output_root));
+    assert_eq!(trajectory.threads, 1);
+
+    std::fs::remove_dir_all(&output_root).unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_step_lone_defector_invades_cubic() {
+    let defectors = defectors_after_step(
+        Trajectory::builder("test_step_lone_defector_invades_cubic")
+            .dimension((5, 5))
+            .layers(5)
+            .geometry(Geometry::Cubic)
+            .neighbourhood(Neighbourhood::cubic(CubicShell::Faces)),
+    );
+
+    // the defector in the middle of the cube takes its six face neighbours
+    assert_eq!(
+        defectors,
+        [
+            (1, 2, 2),
+            (2, 1, 2),
+            (2, 2, 1),
+            (2, 2, 2),
+            (2, 2, 3),
+            (2, 3, 2),
+            (3, 2, 2)
+        ]
+    );
+}